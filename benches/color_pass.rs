@@ -0,0 +1,60 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use sdl2::pixels::Color;
+
+use binvis::{
+    image::Image,
+    color::{ColorPass, Colormap}
+};
+
+
+fn counts(size: usize, max: u32) -> Image<u32>
+{
+    let mut state: u64 = 1;
+    let data = (0..size * size).map(|_|
+    {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+        ((state >> 33) as u32) % max
+    }).collect();
+
+    Image::from_data(size, size, data)
+}
+
+fn color_pass(c: &mut Criterion)
+{
+    let mut group = c.benchmark_group("color_pass_4096");
+    group.sample_size(10);
+
+    // small counts and ones in the thousands like a real file gives the digraph
+    for (max, gamma) in [(64, 1.0), (64, 2.2), (20_000, 1.0), (20_000, 2.2)]
+    {
+        let counts = counts(4096, max);
+
+        let pass = ColorPass::new(max as f64 / 4.0, gamma, Colormap::Gray);
+
+        group.bench_function(format!("scalar_max_{max}_gamma_{gamma}"), |b|
+        {
+            b.iter(||
+            {
+                let colors: Vec<Color> = counts.data().iter()
+                    .map(|&value| pass.color(value))
+                    .collect();
+
+                black_box(colors)
+            })
+        });
+
+        group.bench_function(format!("batched_max_{max}_gamma_{gamma}"), |b|
+        {
+            b.iter(|| black_box(pass.apply(black_box(&counts))))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, color_pass);
+criterion_main!(benches);
@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    io,
+    fmt::Write,
+    collections::HashSet,
+    path::{Path, PathBuf}
+};
+
+use crate::{
+    budget,
+    config::Config,
+    entropy::histogram_entropy,
+    export::{save_png, save_png_with_text, check_not_input, render_export, metadata}
+};
+
+
+const THUMBNAIL_SIZE: usize = 128;
+
+pub struct BatchEntry
+{
+    pub input: String,
+    pub image: PathBuf,
+    pub thumbnail: PathBuf,
+    pub size: usize,
+    pub entropy: f64
+}
+
+fn unique_name(input: &str, used: &mut HashSet<String>) -> String
+{
+    let base = Path::new(input).file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "input".to_owned());
+
+    let mut name = base.clone();
+    let mut index = 1;
+    while !used.insert(name.clone())
+    {
+        name = format!("{base}_{index}");
+        index += 1;
+    }
+
+    name
+}
+
+// renders every input to directory/NAME.png and a small copy to directory/thumbnails/NAME.png
+// a file that fails gets reported and skipped, the rest still get rendered
+pub fn export_batch(config: &Config, directory: &Path) -> (Vec<BatchEntry>, Vec<(String, io::Error)>)
+{
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    let thumbnails = directory.join("thumbnails");
+    if let Err(err) = fs::create_dir_all(&thumbnails)
+    {
+        errors.push((directory.display().to_string(), err));
+
+        return (entries, errors);
+    }
+
+    let mut used = HashSet::new();
+    for input in &config.inputs
+    {
+        let name = format!("{}.png", unique_name(input, &mut used));
+
+        let entry = BatchEntry{
+            input: input.clone(),
+            image: directory.join(&name),
+            thumbnail: thumbnails.join(&name),
+            size: 0,
+            entropy: 0.0
+        };
+
+        match export_entry(config, entry)
+        {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push((input.clone(), err))
+        }
+    }
+
+    (entries, errors)
+}
+
+fn export_entry(config: &Config, mut entry: BatchEntry) -> io::Result<BatchEntry>
+{
+    let config = budget::located(Path::new(&entry.input), &Config{input: entry.input.clone(), ..config.clone()})?;
+
+    check_not_input(&config, &entry.image)?;
+
+    let input = budget::load(Path::new(&entry.input), &config)?;
+
+    let image = render_export(&input, &config);
+    save_png_with_text(&entry.image, &image, &metadata(&config, input.len()))?;
+
+    let scale = THUMBNAIL_SIZE as f64 / image.width().max(image.height()) as f64;
+    let thumbnail = if scale < 1.0
+    {
+        let width = ((image.width() as f64 * scale) as usize).max(1);
+        let height = ((image.height() as f64 * scale) as usize).max(1);
+
+        image.resize(width, height)
+    }
+    else
+    {
+        image
+    };
+
+    save_png(&entry.thumbnail, &thumbnail)?;
+
+    entry.size = input.len();
+    entry.entropy = histogram_entropy(&input.histogram());
+
+    Ok(entry)
+}
+
+fn escape_html(text: &str) -> String
+{
+    text.chars().fold(String::new(), |mut escaped, c|
+    {
+        match c
+        {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c)
+        }
+
+        escaped
+    })
+}
+
+// relative to the index when its inside the same directory tree so the report can be moved around
+fn link(index_directory: &Path, path: &Path) -> String
+{
+    let absolute = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_owned());
+
+    let path = absolute(path);
+    let relative = path.strip_prefix(absolute(index_directory)).unwrap_or(&path);
+
+    let parts: Vec<_> = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect();
+
+    escape_html(&parts.join("/"))
+}
+
+pub fn write_index(path: &Path, entries: &[BatchEntry]) -> io::Result<()>
+{
+    let directory = match path.parent()
+    {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+        _ => PathBuf::from(".")
+    };
+
+    let mut html = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>binvis</title>\n",
+        "<style>\n",
+        "body { background: #111; color: #ddd; font-family: monospace; }\n",
+        ".grid { display: flex; flex-wrap: wrap; gap: 16px; }\n",
+        ".cell { width: 160px; word-break: break-all; }\n",
+        ".cell img { width: 128px; image-rendering: pixelated; }\n",
+        "a { color: #8cf; }\n",
+        "</style>\n</head>\n<body>\n<div class=\"grid\">\n"
+    ));
+
+    for entry in entries
+    {
+        writeln!(
+            html,
+            "<div class=\"cell\"><a href=\"{}\"><img src=\"{}\"></a><br>{}<br>{} bytes<br>entropy {:.3}</div>",
+            link(&directory, &entry.image),
+            link(&directory, &entry.thumbnail),
+            escape_html(&entry.input),
+            entry.size,
+            entry.entropy
+        ).unwrap();
+    }
+
+    html.push_str("</div>\n</body>\n</html>\n");
+
+    fs::write(path, html)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn batch_with_index()
+    {
+        let directory = env::temp_dir().join(format!("binvis_batch_test_{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let first = directory.join("first.bin");
+        let second = directory.join("<second>");
+        fs::write(&first, (0..5000_u32).map(|x| x as u8).collect::<Vec<_>>()).unwrap();
+        fs::write(&second, [0_u8; 300]).unwrap();
+
+        let images = directory.join("images");
+        let index = directory.join("index.html");
+
+        let args = [
+            "binvis",
+            first.to_str().unwrap(),
+            second.to_str().unwrap(),
+            "missing_file_for_binvis_batch_test",
+            "--output-dir",
+            images.to_str().unwrap()
+        ];
+
+        let config = Config::parse(args.map(String::from)).unwrap();
+
+        let (entries, errors) = export_batch(&config, &images);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(errors.len(), 1);
+
+        assert_eq!(entries[0].size, 5000);
+        assert!((entries[0].entropy - 8.0).abs() < 0.1);
+        assert_eq!(entries[1].entropy, 0.0);
+        assert!(entries.iter().all(|entry| entry.image.exists() && entry.thumbnail.exists()));
+
+        write_index(&index, &entries).unwrap();
+
+        let html = fs::read_to_string(&index).unwrap();
+        assert!(html.contains("href=\"images/first.bin.png\""));
+        assert!(html.contains("src=\"images/thumbnails/first.bin.png\""));
+        assert!(html.contains("&lt;second&gt;"));
+        assert!(!html.contains("<second>"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
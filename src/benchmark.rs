@@ -0,0 +1,267 @@
+use std::{
+    io,
+    fmt,
+    hint::black_box,
+    path::Path,
+    time::{Instant, Duration}
+};
+
+use sdl2::pixels::Color;
+
+use crate::{
+    input,
+    digraph_counts,
+    config::Config,
+    image::Image,
+    budget::{self, Loaded},
+    render::{render, render_digraph, render_accumulated, VisualizationMode}
+};
+
+
+pub struct Stage
+{
+    pub name: &'static str,
+    pub duration: Duration
+}
+
+pub struct Timings
+{
+    pub bytes: usize,
+    pub stages: Vec<Stage>,
+    pub checksum: u64
+}
+
+impl Timings
+{
+    // everything except reading the file
+    pub fn compute(&self) -> Duration
+    {
+        self.stages.iter().filter(|stage| stage.name != "read").map(|stage| stage.duration).sum()
+    }
+}
+
+impl fmt::Display for Timings
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        for stage in &self.stages
+        {
+            writeln!(f, "{}: {:.2?}", stage.name, stage.duration)?;
+        }
+
+        writeln!(f, "throughput: {:.1} MB/s", throughput(self.bytes, self.compute()))?;
+        write!(f, "checksum: {:016x}", self.checksum)
+    }
+}
+
+pub fn throughput(bytes: usize, duration: Duration) -> f64
+{
+    bytes as f64 / 1_000_000.0 / duration.as_secs_f64().max(f64::EPSILON)
+}
+
+// fnv-1a so the result has to actually be computed and can be compared between runs
+pub fn checksum(image: &Image<Color>) -> u64
+{
+    image.data().iter().flat_map(|c| [c.r, c.g, c.b, c.a]).fold(0xcbf29ce484222325, |hash, x|
+    {
+        (hash ^ x as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn timed<T>(stages: &mut Vec<Stage>, name: &'static str, f: impl FnOnce() -> T) -> T
+{
+    let start = Instant::now();
+    let value = black_box(f());
+
+    stages.push(Stage{name, duration: start.elapsed()});
+
+    value
+}
+
+pub fn time_render(bytes: &[u8], config: &Config, stages: &mut Vec<Stage>) -> Image<Color>
+{
+    if config.mode == VisualizationMode::Digraph
+    {
+        let counts = timed(stages, "fill", || digraph_counts(black_box(bytes)));
+
+        timed(stages, "color", || render_digraph(&counts, bytes.len(), config))
+    }
+    else
+    {
+        timed(stages, "render", || render(black_box(bytes), config))
+    }
+}
+
+// the whole pipeline without a window or any output file
+pub fn compute_only(config: &Config) -> io::Result<Timings>
+{
+    let mut stages = Vec::new();
+
+    let config = &budget::located(Path::new(&config.input), config)?;
+
+    // over --max-memory the streamable modes do most of their work while reading
+    let input = timed(&mut stages, "read", || budget::load(Path::new(&config.input), config))?;
+
+    let image = match &input
+    {
+        Loaded::Bytes(bytes) => time_render(bytes, config, &mut stages),
+        Loaded::Accumulated(accumulator) => timed(&mut stages, "render", || render_accumulated(accumulator, config))
+    };
+
+    Ok(Timings{bytes: input.len(), stages, checksum: checksum(&image)})
+}
+
+const SYNTHETIC_SIZE: usize = 1 << 24;
+
+// a quarter each of zeros, text, noise and a repeating structure so no mode gets an easy time
+pub fn synthetic_input() -> Vec<u8>
+{
+    let quarter = SYNTHETIC_SIZE / 4;
+
+    let mut state: u64 = 0x9e3779b97f4a7c15;
+    let mut random = move ||
+    {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        state
+    };
+
+    let text = (0..quarter).map(|i| b"the quick brown fox jumps over the lazy dog\n"[i % 44]);
+    let noise = (0..quarter).map(|_| random() as u8);
+    let structure = (0..quarter).map(|i| [0x7f, 0x45, 0x4c, 0x46, (i >> 4) as u8, 0, 0, 0x10][i % 8]);
+
+    (0..quarter).map(|_| 0).chain(text).chain(noise).chain(structure).collect()
+}
+
+pub struct ModeTiming
+{
+    pub mode: &'static str,
+    // why the mode got skipped if the options dont work with it
+    pub duration: Result<Duration, String>
+}
+
+pub struct Benchmark
+{
+    pub bytes: usize,
+    pub modes: Vec<ModeTiming>
+}
+
+impl fmt::Display for Benchmark
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let width = self.modes.iter().map(|timing| timing.mode.len()).max().unwrap_or(0).max(4);
+
+        writeln!(f, "{:width$}  {:>10}  {:>10}", "mode", "time", "MB/s")?;
+
+        for timing in &self.modes
+        {
+            match &timing.duration
+            {
+                Ok(duration) =>
+                {
+                    let throughput = throughput(self.bytes, *duration);
+                    let duration = format!("{duration:.2?}");
+
+                    writeln!(f, "{:width$}  {duration:>10}  {throughput:>10.1}", timing.mode)?;
+                },
+                Err(err) => writeln!(f, "{:width$}  skipped, {err}", timing.mode)?
+            }
+        }
+
+        write!(f, "({} bytes)", self.bytes)
+    }
+}
+
+// renders the same bytes in every mode with the rest of the options as given
+pub fn benchmark_modes(bytes: &[u8], config: &Config) -> Benchmark
+{
+    let modes = VisualizationMode::NAMES.iter().map(|&(mode_name, mode)|
+    {
+        let config = Config{
+            mode,
+            // records cant render without a record size so it gets a typical one
+            record_size: config.record_size.or(Some(64)),
+            ..config.clone()
+        };
+
+        // the options were only checked against the mode that was given
+        let duration = config.validate().map(|_|
+        {
+            let mut stages = Vec::new();
+            time_render(bytes, &config, &mut stages);
+
+            stages.iter().map(|stage| stage.duration).sum()
+        });
+
+        ModeTiming{mode: mode_name, duration}
+    }).collect();
+
+    Benchmark{bytes: bytes.len(), modes}
+}
+
+// the input if theres one and made up bytes otherwise
+pub fn benchmark(config: &Config) -> io::Result<Benchmark>
+{
+    let bytes = if config.input.is_empty()
+    {
+        synthetic_input()
+    }
+    else
+    {
+        let path = Path::new(&config.input);
+
+        if let Some(total) = budget::over_budget(path, config)?
+        {
+            return Err(io::Error::other(format!(
+                "every mode gets benchmarked so all {total} bytes have to be in memory which is over --max-memory"
+            )));
+        }
+
+        input::read_input(path, input::skip_header(path, config.selection, config.header)?)?
+    };
+
+    Ok(benchmark_modes(&bytes, config))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn benchmarks_every_mode()
+    {
+        let config = Config::parse(["binvis", "benchmark"].map(String::from)).unwrap();
+
+        let bytes: Vec<u8> = synthetic_input().into_iter().step_by(256).collect();
+        let benchmark = benchmark_modes(&bytes, &config);
+
+        assert_eq!(benchmark.modes.len(), VisualizationMode::NAMES.len());
+
+        let table = benchmark.to_string();
+        assert!(VisualizationMode::NAMES.iter().all(|(mode, _)| table.contains(mode)));
+        assert_eq!(table.lines().count(), VisualizationMode::NAMES.len() + 2);
+        assert!(benchmark.modes.iter().all(|timing| timing.duration.is_ok()));
+    }
+
+    #[test]
+    fn skips_modes_the_options_dont_work_with()
+    {
+        let config = Config::parse(["binvis", "benchmark", "--size", "300"].map(String::from)).unwrap();
+
+        let benchmark = benchmark_modes(&[1, 2, 3, 4, 5], &config);
+
+        let skipped: Vec<_> = benchmark.modes.iter()
+            .filter(|timing| timing.duration.is_err())
+            .map(|timing| timing.mode)
+            .collect();
+        assert_eq!(skipped, ["hilbert", "selfdiff", "opcodes", "hamming"]);
+
+        let table = benchmark.to_string();
+        let hilbert = table.lines().find(|line| line.starts_with("hilbert ")).unwrap();
+        assert!(hilbert.ends_with("skipped, --size must be a power of 2 for the hilbert layout (got 300)"), "{hilbert}");
+    }
+}
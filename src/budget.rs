@@ -0,0 +1,422 @@
+use std::{
+    io::{self, Read},
+    path::Path
+};
+
+use sdl2::pixels::Color;
+
+use crate::{
+    input,
+    put_points,
+    digraph_counts,
+    config::Config,
+    image::{Image, Pos2},
+    entropy::entropy,
+    stats::histogram,
+    layout::bytes_per_cell,
+    render::{render, render_accumulated, VisualizationMode}
+};
+
+
+// builds up what a streamable mode needs a chunk at a time so the input never has to be in memory all at once
+pub struct Accumulator
+{
+    mode: VisualizationMode,
+    len: usize,
+    previous: Option<u8>,
+    counts: Image<u32>,
+    histogram: [u64; 256],
+    // entropy mode keeps the start of the current cell (up to the window) and the entropies of the finished ones
+    step: usize,
+    window: usize,
+    in_step: usize,
+    pending: Vec<u8>,
+    entropies: Vec<f64>
+}
+
+impl Accumulator
+{
+    // whether the mode can be built up a chunk at a time, total is how many bytes are going to be added
+    pub fn supports(total: usize, config: &Config) -> bool
+    {
+        let step = bytes_per_cell(total, config.size * config.size);
+
+        match config.mode
+        {
+            VisualizationMode::Digraph | VisualizationMode::Pmi | VisualizationMode::Presence => true,
+            // overlapping windows would need the bytes from the cell before
+            VisualizationMode::Entropy => config.entropy_window.unwrap_or(step) <= step,
+            _ => false
+        }
+    }
+
+    // none if the mode needs all of the bytes at once
+    pub fn new(total: usize, config: &Config) -> Option<Self>
+    {
+        if !Self::supports(total, config)
+        {
+            return None;
+        }
+
+        let step = bytes_per_cell(total, config.size * config.size);
+        let window = config.entropy_window.unwrap_or(step);
+
+        Some(Self{
+            mode: config.mode,
+            len: 0,
+            previous: None,
+            counts: Image::new(256, 256, 0),
+            histogram: [0; 256],
+            step,
+            window,
+            in_step: 0,
+            pending: Vec::new(),
+            entropies: Vec::new()
+        })
+    }
+
+    pub fn add(&mut self, chunk: &[u8])
+    {
+        // the pair going across the chunk boundary
+        if let (Some(previous), Some(&first)) = (self.previous, chunk.first())
+        {
+            let count = &mut self.counts[Pos2{x: previous as usize, y: first as usize}];
+            *count = count.saturating_add(1);
+        }
+
+        put_points(&mut self.counts, chunk);
+
+        self.previous = chunk.last().copied().or(self.previous);
+        self.len += chunk.len();
+
+        chunk.iter().for_each(|&x| self.histogram[x as usize] += 1);
+
+        if self.mode == VisualizationMode::Entropy
+        {
+            self.add_entropy(chunk);
+        }
+    }
+
+    fn add_entropy(&mut self, mut chunk: &[u8])
+    {
+        while !chunk.is_empty()
+        {
+            let (now, later) = chunk.split_at((self.step - self.in_step).min(chunk.len()));
+
+            let wanted = self.window.saturating_sub(self.in_step).min(now.len());
+            self.pending.extend_from_slice(&now[..wanted]);
+
+            self.in_step += now.len();
+            if self.in_step == self.step
+            {
+                self.entropies.push(entropy(&self.pending));
+
+                self.pending.clear();
+                self.in_step = 0;
+            }
+
+            chunk = later;
+        }
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.len == 0
+    }
+
+    pub fn counts(&self) -> &Image<u32>
+    {
+        &self.counts
+    }
+
+    pub fn histogram(&self) -> &[u64; 256]
+    {
+        &self.histogram
+    }
+
+    // including the last cell even if it didnt fill up
+    pub fn entropies(&self) -> Vec<f64>
+    {
+        let mut entropies = self.entropies.clone();
+
+        if self.in_step != 0
+        {
+            entropies.push(entropy(&self.pending));
+        }
+
+        entropies
+    }
+}
+
+// the input as read for visualizing, all of it if its under --max-memory and only whats accumulated otherwise
+pub enum Loaded
+{
+    Bytes(Vec<u8>),
+    Accumulated(Box<Accumulator>)
+}
+
+impl Loaded
+{
+    pub fn len(&self) -> usize
+    {
+        match self
+        {
+            Self::Bytes(bytes) => bytes.len(),
+            Self::Accumulated(accumulator) => accumulator.len()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+    // empty if they were only accumulated
+    pub fn bytes(&self) -> &[u8]
+    {
+        match self
+        {
+            Self::Bytes(bytes) => bytes,
+            Self::Accumulated(_) => &[]
+        }
+    }
+
+    pub fn histogram(&self) -> [u64; 256]
+    {
+        match self
+        {
+            Self::Bytes(bytes) => histogram(bytes),
+            Self::Accumulated(accumulator) => *accumulator.histogram()
+        }
+    }
+
+    pub fn digraph_counts(&self) -> Image<u32>
+    {
+        match self
+        {
+            Self::Bytes(bytes) => digraph_counts(bytes),
+            Self::Accumulated(accumulator) => accumulator.counts().clone()
+        }
+    }
+
+    pub fn render(&self, config: &Config) -> Image<Color>
+    {
+        match self
+        {
+            Self::Bytes(bytes) => render(bytes, config),
+            Self::Accumulated(accumulator) => render_accumulated(accumulator, config)
+        }
+    }
+}
+
+// how many bytes get visualized if thats more than --max-memory, none if they fit or theres no limit
+// fifos dont have a length up front so they cant be checked against the limit at all
+pub fn over_budget(path: &Path, config: &Config) -> io::Result<Option<u64>>
+{
+    let Some(max_memory) = config.max_memory
+    else
+    {
+        return Ok(None);
+    };
+
+    if input::is_fifo(path)
+    {
+        return Err(io::Error::other("--max-memory needs to know the input length up front so it cant read a fifo"));
+    }
+
+    let selection = input::skip_header(path, config.selection, config.header)?;
+
+    let file_length = path.metadata()?.len();
+
+    let (offset, length) = selection.resolve(file_length);
+    let selected = file_length.saturating_sub(offset).min(length.unwrap_or(u64::MAX));
+
+    Ok((selected > max_memory).then_some(selected))
+}
+
+// the config with file_offset set to where in the file the visualized bytes start, after the header and the selection
+// a fifo has no length up front so a percentage of it counts as starting at 0
+pub fn located(path: &Path, config: &Config) -> io::Result<Config>
+{
+    let selection = input::skip_header(path, config.selection, config.header)?;
+
+    let file_offset = selection.resolve(path.metadata()?.len()).0;
+
+    Ok(Config{file_offset, ..config.clone()})
+}
+
+fn needs_everything(total: u64, config: &Config) -> String
+{
+    let mode = VisualizationMode::NAMES.iter().find(|(_, mode)| *mode == config.mode).map(|(name, _)| *name);
+
+    format!(
+        "{} mode needs all {total} bytes of {} in memory which is over --max-memory {} \
+         (only digraph, pmi, presence and entropy with no --entropy-window bigger than a cell can stream)",
+        mode.unwrap_or("this"),
+        config.input,
+        config.max_memory.unwrap_or(0)
+    )
+}
+
+// errors for what cant work with the input over --max-memory, so they show up before reading any of it
+pub fn check(path: &Path, config: &Config) -> Result<(), String>
+{
+    let over = over_budget(path, config).map_err(|err| format!("cant open {}: {err}", config.input))?;
+
+    let Some(total) = over
+    else
+    {
+        return Ok(());
+    };
+
+    if config.animate
+    {
+        return Err(format!(
+            "--animate needs all {total} bytes of {} in memory which is over --max-memory {}",
+            config.input,
+            config.max_memory.unwrap_or(0)
+        ));
+    }
+
+    if !Accumulator::supports(total as usize, config)
+    {
+        return Err(needs_everything(total, config));
+    }
+
+    Ok(())
+}
+
+pub fn load(path: &Path, config: &Config) -> io::Result<Loaded>
+{
+    let over = over_budget(path, config)?;
+
+    let selection = input::skip_header(path, config.selection, config.header)?;
+
+    let Some(total) = over
+    else
+    {
+        return input::read_input(path, selection).map(Loaded::Bytes);
+    };
+
+    let mut accumulator = Accumulator::new(total as usize, config).ok_or_else(||
+    {
+        io::Error::other(needs_everything(total, config))
+    })?;
+
+    let mut reader = input::reader(path, selection)?;
+
+    let mut buffer = vec![0; input::CHUNK_SIZE];
+    loop
+    {
+        match reader.read(&mut buffer)
+        {
+            Ok(0) => break,
+            Ok(amount) => accumulator.add(&buffer[..amount]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err)
+        }
+    }
+
+    Ok(Loaded::Accumulated(Box::new(accumulator)))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{env, fs, process};
+
+    use crate::benchmark::checksum;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Config
+    {
+        Config::parse(["binvis", "file"].iter().chain(args).map(|x| x.to_string())).unwrap()
+    }
+
+    #[test]
+    fn accumulating_matches_reading_everything()
+    {
+        let bytes: Vec<u8> = (0..100_003_u64).map(|x| (x * x / 7 % 253) as u8).collect();
+
+        for args in [&["--mode", "digraph"][..], &["--mode", "pmi"], &["--mode", "presence"], &["--mode", "entropy"]]
+        {
+            let config = parse(args);
+
+            let mut accumulator = Accumulator::new(bytes.len(), &config).unwrap();
+
+            // uneven chunks so the pairs and cells going across them get checked
+            for chunk in bytes.chunks(777)
+            {
+                accumulator.add(chunk);
+            }
+
+            assert_eq!(accumulator.len(), bytes.len());
+            assert_eq!(accumulator.counts(), &digraph_counts(&bytes));
+            assert_eq!(accumulator.histogram(), &histogram(&bytes));
+
+            let accumulated = render_accumulated(&accumulator, &config);
+            assert_eq!(checksum(&accumulated), checksum(&render(&bytes, &config)), "{args:?}");
+        }
+
+        assert!(Accumulator::new(bytes.len(), &parse(&["--mode", "hilbert", "--size", "64"])).is_none());
+        assert!(Accumulator::new(bytes.len(), &parse(&["--mode", "entropy", "--entropy-window", "1000000"])).is_none());
+    }
+
+    #[test]
+    fn counts_saturate()
+    {
+        let mut accumulator = Accumulator::new(1 << 40, &parse(&[])).unwrap();
+
+        // as if almost 4Gi zeros were already added
+        accumulator.counts[Pos2{x: 0, y: 0}] = u32::MAX - 2;
+
+        accumulator.add(&[0, 0]);
+        accumulator.add(&[0, 0, 0]);
+        accumulator.add(&[0]);
+
+        assert_eq!(accumulator.counts()[Pos2{x: 0, y: 0}], u32::MAX);
+        assert_eq!(accumulator.histogram()[0], 6);
+    }
+
+    #[test]
+    fn over_budget()
+    {
+        let path = env::temp_dir().join(format!("binvis_budget_test_{}", process::id()));
+
+        let bytes: Vec<u8> = (0..5000_u32).map(|x| (x % 251) as u8).collect();
+        fs::write(&path, &bytes).unwrap();
+
+        let load_with = |args: &[&str]|
+        {
+            let config = Config{input: path.display().to_string(), ..parse(args)};
+
+            load(&path, &config)
+        };
+
+        let under = load_with(&["--max-memory", "5000"]).unwrap();
+        let over = load_with(&["--max-memory", "4999"]).unwrap();
+        let over_selected = load_with(&["--max-memory", "1K", "--offset", "4000"]).unwrap();
+        let needs_bytes = load_with(&["--max-memory", "1K", "--mode", "hilbert", "--size", "64"]);
+
+        let animated = Config{animate: true, ..Config{input: path.display().to_string(), ..parse(&["--max-memory", "1K"])}};
+        let animate_error = check(&path, &animated);
+        let fine = check(&path, &Config{input: path.display().to_string(), ..parse(&["--max-memory", "1K"])});
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(animate_error.unwrap_err().contains("--animate"));
+        assert!(fine.is_ok());
+
+        assert!(matches!(under, Loaded::Bytes(ref read) if *read == bytes));
+        assert!(matches!(over, Loaded::Accumulated(_)));
+        assert_eq!(over.digraph_counts(), digraph_counts(&bytes));
+        assert!(matches!(over_selected, Loaded::Bytes(ref read) if read[..] == bytes[4000..]));
+        assert!(needs_bytes.is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fmt,
+    io,
+    path::Path
+};
+
+use sdl2::pixels::Color;
+
+use crate::{
+    budget,
+    config::Config,
+    image::Image,
+    export::{load_png, render_export}
+};
+
+
+pub enum Check
+{
+    Compared{differing: usize, total: usize, tolerance: usize},
+    // sizes are width and height
+    WrongSize{image: (usize, usize), baseline: (usize, usize)}
+}
+
+impl Check
+{
+    pub fn passed(&self) -> bool
+    {
+        match *self
+        {
+            Self::Compared{differing, tolerance, ..} => differing <= tolerance,
+            Self::WrongSize{..} => false
+        }
+    }
+}
+
+impl fmt::Display for Check
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            Self::Compared{differing, total, tolerance} =>
+            {
+                write!(f, "{differing} of {total} pixels differ from the baseline (tolerance {tolerance})")
+            },
+            Self::WrongSize{image, baseline} =>
+            {
+                write!(f, "the image is {}x{} but the baseline is {}x{}", image.0, image.1, baseline.0, baseline.1)
+            }
+        }
+    }
+}
+
+// none if theyre not the same size
+pub fn differing_pixels(image: &Image<Color>, baseline: &Image<Color>) -> Option<usize>
+{
+    if (image.width(), image.height()) != (baseline.width(), baseline.height())
+    {
+        return None;
+    }
+
+    Some(image.data().iter().zip(baseline.data()).filter(|(a, b)| a != b).count())
+}
+
+// renders the input like exporting it would and compares that to the baseline image
+pub fn check(config: &Config, baseline: &Path) -> io::Result<Check>
+{
+    let config = &budget::located(Path::new(&config.input), config)?;
+
+    let image = render_export(&budget::load(Path::new(&config.input), config)?, config);
+
+    let baseline = load_png(baseline)?;
+
+    let check = match differing_pixels(&image, &baseline)
+    {
+        Some(differing) => Check::Compared{differing, total: image.data().len(), tolerance: config.tolerance.unwrap_or(0)},
+        None => Check::WrongSize{
+            image: (image.width(), image.height()),
+            baseline: (baseline.width(), baseline.height())
+        }
+    };
+
+    Ok(check)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{env, fs, process};
+
+    use crate::{export::save_png, image::Pos2};
+
+    use super::*;
+
+    #[test]
+    fn checks_against_baseline()
+    {
+        let input = env::temp_dir().join(format!("binvis_check_input_{}", process::id()));
+        let baseline = env::temp_dir().join(format!("binvis_check_baseline_{}.png", process::id()));
+
+        let bytes: Vec<u8> = (0..5000_u32).map(|x| (x * 31 % 256) as u8).collect();
+        fs::write(&input, &bytes).unwrap();
+
+        let config = |args: &[&str]|
+        {
+            let base = ["binvis", "check", input.to_str().unwrap(), "--baseline-image", baseline.to_str().unwrap()];
+
+            Config::parse(base.iter().chain(args).map(|x| x.to_string())).unwrap()
+        };
+
+        let mut image = render_export(&budget::load(&input, &config(&[])).unwrap(), &config(&[]));
+        save_png(&baseline, &image).unwrap();
+
+        let same = check(&config(&[]), &baseline).unwrap();
+
+        image[Pos2{x: 0, y: 0}] = Color::RGB(1, 2, 3);
+        image[Pos2{x: 5, y: 9}] = Color::RGB(1, 2, 3);
+        save_png(&baseline, &image).unwrap();
+
+        let drifted = check(&config(&[]), &baseline).unwrap();
+        let tolerated = check(&config(&["--tolerance", "2"]), &baseline).unwrap();
+        let resized = check(&config(&["--mode", "hilbert", "--size", "64"]), &baseline).unwrap();
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&baseline).unwrap();
+
+        assert!(same.passed(), "{same}");
+        assert!(!drifted.passed());
+        assert_eq!(drifted.to_string(), "2 of 65536 pixels differ from the baseline (tolerance 0)");
+        assert!(tolerated.passed());
+        assert!(!resized.passed());
+        assert_eq!(resized.to_string(), "the image is 64x64 but the baseline is 256x256");
+    }
+}
@@ -0,0 +1,450 @@
+use sdl2::pixels::Color;
+
+use crate::{image::Image, stats::ByteClass};
+
+
+// counts past this go through powf one by one instead of a lookup table
+const COUNTS_TABLE_LIMIT: u32 = 1 << 16;
+
+const CHUNK_SIZE: usize = 4096;
+
+const VIRIDIS: &[(u8, u8, u8)] = &[
+    (68, 1, 84),
+    (59, 82, 139),
+    (33, 145, 140),
+    (94, 201, 98),
+    (253, 231, 37)
+];
+
+const MAGMA: &[(u8, u8, u8)] = &[
+    (0, 0, 4),
+    (59, 15, 112),
+    (140, 41, 129),
+    (222, 73, 104),
+    (254, 159, 109),
+    (252, 253, 191)
+];
+
+const HOT: &[(u8, u8, u8)] = &[
+    (0, 0, 0),
+    (230, 0, 0),
+    (255, 210, 0),
+    (255, 255, 255)
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap
+{
+    Gray,
+    Viridis,
+    Magma,
+    Hot,
+    // straight line between two colors, from --gradient instead of a name
+    Gradient(Color, Color)
+}
+
+impl Colormap
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("gray", Self::Gray),
+        ("viridis", Self::Viridis),
+        ("magma", Self::Magma),
+        ("hot", Self::Hot)
+    ];
+
+    pub fn color(&self, value: u8) -> Color
+    {
+        match self
+        {
+            Self::Gray => Color::RGB(value, value, value),
+            Self::Viridis => gradient(VIRIDIS, value),
+            Self::Magma => gradient(MAGMA, value),
+            Self::Hot => gradient(HOT, value),
+            Self::Gradient(from, to) =>
+            {
+                let stops = [(from.r, from.g, from.b), (to.r, to.g, to.b)];
+
+                gradient(&stops, value)
+            }
+        }
+    }
+
+    // for values from 0 to 1
+    pub fn color_fraction(&self, value: f64) -> Color
+    {
+        self.color((value * 255.0).clamp(0.0, 255.0) as u8)
+    }
+
+    pub fn lut(&self) -> [Color; 256]
+    {
+        std::array::from_fn(|i| self.color(i as u8))
+    }
+}
+
+// log maps 1/LOG_RANGE of the top value to about the middle of the colormap
+const LOG_RANGE: f64 = 255.0;
+
+// how values already scaled to go from 0 to 1 get spread over the colormap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode
+{
+    Linear,
+    Sqrt,
+    Log
+}
+
+impl ScaleMode
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("linear", Self::Linear),
+        ("sqrt", Self::Sqrt),
+        ("log", Self::Log)
+    ];
+
+    pub fn apply(&self, value: f64) -> f64
+    {
+        let value = value.max(0.0);
+
+        match self
+        {
+            Self::Linear => value,
+            Self::Sqrt => value.sqrt(),
+            Self::Log => (value * LOG_RANGE).ln_1p() / LOG_RANGE.ln_1p()
+        }
+    }
+
+    // the ones that are just a power can go through the faster ColorPass
+    pub fn gamma(&self) -> Option<f64>
+    {
+        match self
+        {
+            Self::Linear => Some(1.0),
+            Self::Sqrt => Some(0.5),
+            Self::Log => None
+        }
+    }
+}
+
+// opacity after drawing count points that each cover saturation of whats under them
+pub fn accumulated_alpha(count: u32, saturation: f64) -> f64
+{
+    1.0 - (1.0 - saturation).powf(count as f64)
+}
+
+pub fn class_color(class: ByteClass) -> Color
+{
+    match class
+    {
+        ByteClass::Null => Color::RGB(0, 0, 0),
+        ByteClass::Control => Color::RGB(77, 175, 74),
+        ByteClass::Printable => Color::RGB(55, 126, 184),
+        ByteClass::High => Color::RGB(228, 26, 28)
+    }
+}
+
+// straight (not premultiplied) alpha lerp of the color channels, alpha of under is kept
+// only right for an opaque under, Image::blend handles transparency properly
+pub fn blend(under: Color, over: Color, alpha: f64) -> Color
+{
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * alpha).round() as u8;
+
+    Color::RGBA(lerp(under.r, over.r), lerp(under.g, over.g), lerp(under.b, over.b), under.a)
+}
+
+// all of the arguments and results go from 0 to 1
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> Color
+{
+    let hue = hue.rem_euclid(1.0) * 6.0;
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let m = value - chroma;
+
+    let (r, g, b) = match hue as u32
+    {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x)
+    };
+
+    let channel = |c: f64| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    Color::RGB(channel(r), channel(g), channel(b))
+}
+
+pub fn rgb_to_hsv(color: Color) -> (f64, f64, f64)
+{
+    let [r, g, b] = [color.r, color.g, color.b].map(|x| x as f64 / 255.0);
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0
+    {
+        0.0
+    }
+    else if max == r
+    {
+        ((g - b) / delta).rem_euclid(6.0)
+    }
+    else if max == g
+    {
+        (b - r) / delta + 2.0
+    }
+    else
+    {
+        (r - g) / delta + 4.0
+    };
+
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue / 6.0, saturation, max)
+}
+
+// evenly spaced stops with linear interpolation between them
+fn gradient(stops: &[(u8, u8, u8)], value: u8) -> Color
+{
+    let position = value as f64 / 255.0 * (stops.len() - 1) as f64;
+
+    let index = (position.floor() as usize).min(stops.len() - 2);
+    let t = position - index as f64;
+
+    let (a, b) = (stops[index], stops[index + 1]);
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+    Color::RGB(lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+pub struct ColorPass
+{
+    top: f64,
+    gamma: f64,
+    lut: [Color; 256]
+}
+
+impl ColorPass
+{
+    pub fn new(top: f64, gamma: f64, colormap: Colormap) -> Self
+    {
+        Self{top, gamma, lut: colormap.lut()}
+    }
+
+    pub fn intensity(&self, value: u32) -> u8
+    {
+        let value = (value as f64 / self.top).powf(self.gamma);
+
+        (value * 256.0).clamp(0.0, 255.0) as u8
+    }
+
+    pub fn color(&self, value: u32) -> Color
+    {
+        self.lut[self.intensity(value) as usize]
+    }
+
+    // intensities of 0 to highest so they can be looked up instead of going through powf every time,
+    // none if theres no powf to skip or the values are too big for a table
+    fn table(&self, highest: u32) -> Option<Vec<u8>>
+    {
+        (self.gamma != 1.0 && highest < COUNTS_TABLE_LIMIT).then(||
+        {
+            (0..=highest).map(|value| self.intensity(value)).collect()
+        })
+    }
+
+    // same results as calling intensity on every value, just faster
+    pub fn intensities(&self, values: &[u32], output: &mut [u8])
+    {
+        let table = self.table(values.iter().copied().max().unwrap_or(0));
+
+        self.intensities_with(values, output, table.as_deref());
+    }
+
+    // the table has to cover every value
+    fn intensities_with(&self, values: &[u32], output: &mut [u8], table: Option<&[u8]>)
+    {
+        assert_eq!(values.len(), output.len());
+
+        if self.gamma == 1.0
+        {
+            // powf(1.0) is the identity so skipping it lets this loop vectorize
+            values.iter().zip(output.iter_mut()).for_each(|(&value, output)|
+            {
+                *output = (value as f64 / self.top * 256.0).clamp(0.0, 255.0) as u8;
+            });
+
+            return;
+        }
+
+        match table
+        {
+            Some(table) =>
+            {
+                values.iter().zip(output.iter_mut()).for_each(|(&value, output)|
+                {
+                    *output = table[value as usize];
+                });
+            },
+            None =>
+            {
+                values.iter().zip(output.iter_mut()).for_each(|(&value, output)|
+                {
+                    *output = self.intensity(value);
+                });
+            }
+        }
+    }
+
+    pub fn apply(&self, image: &Image<u32>) -> Image<Color>
+    {
+        // one table for the whole image instead of one for every chunk
+        let table = self.table(image.data().iter().copied().max().unwrap_or(0));
+
+        let mut intensities = [0; CHUNK_SIZE];
+
+        let mut data = Vec::with_capacity(image.data().len());
+        for values in image.data().chunks(CHUNK_SIZE)
+        {
+            let intensities = &mut intensities[..values.len()];
+            self.intensities_with(values, intensities, table.as_deref());
+
+            data.extend(intensities.iter().map(|&intensity| self.lut[intensity as usize]));
+        }
+
+        Image::from_data(image.width(), image.height(), data)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn random_counts(size: usize, max: u32) -> Image<u32>
+    {
+        let mut state: u64 = 12345;
+        let data = (0..size * size).map(|_|
+        {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+            ((state >> 33) as u32) % max
+        }).collect();
+
+        Image::from_data(size, size, data)
+    }
+
+    #[test]
+    fn batched_matches_scalar()
+    {
+        for max in [10, 1000, u32::MAX]
+        {
+            // bigger than a chunk so the table from the whole image gets shared between them
+            let counts = random_counts(128, max);
+
+            for gamma in [1.0, 0.5, 2.2]
+            {
+                let pass = ColorPass::new(max as f64 / 20.0, gamma, Colormap::Gray);
+
+                let batched = pass.apply(&counts);
+                let scalar: Vec<Color> = counts.data().iter().map(|&value| pass.color(value)).collect();
+
+                assert_eq!(batched.data(), &scalar[..], "max: {max}, gamma: {gamma}");
+            }
+        }
+    }
+
+    #[test]
+    fn hsv_known_colors()
+    {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), Color::RGB(255, 0, 0));
+        assert_eq!(hsv_to_rgb(1.0 / 3.0, 1.0, 1.0), Color::RGB(0, 255, 0));
+        assert_eq!(hsv_to_rgb(2.0 / 3.0, 1.0, 0.5), Color::RGB(0, 0, 128));
+        assert_eq!(hsv_to_rgb(0.5, 0.0, 1.0), Color::RGB(255, 255, 255));
+        assert_eq!(hsv_to_rgb(0.75, 1.0, 0.0), Color::RGB(0, 0, 0));
+        assert_eq!(hsv_to_rgb(1.0, 1.0, 1.0), hsv_to_rgb(0.0, 1.0, 1.0));
+
+        assert_eq!(rgb_to_hsv(Color::RGB(255, 255, 0)), (1.0 / 6.0, 1.0, 1.0));
+        assert_eq!(rgb_to_hsv(Color::RGB(0, 0, 0)), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn hsv_roundtrip()
+    {
+        for r in (0..=255).step_by(15)
+        {
+            for g in (0..=255).step_by(17)
+            {
+                for b in (0..=255).step_by(51)
+                {
+                    let color = Color::RGB(r, g, b);
+                    let (h, s, v) = rgb_to_hsv(color);
+
+                    assert_eq!(hsv_to_rgb(h, s, v), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scale_modes_and_accumulation()
+    {
+        for mode in [ScaleMode::Linear, ScaleMode::Sqrt, ScaleMode::Log]
+        {
+            assert_eq!(mode.apply(0.0), 0.0);
+            assert!((mode.apply(1.0) - 1.0).abs() < 1e-12);
+            assert!(mode.apply(0.25) >= ScaleMode::Linear.apply(0.25));
+
+            if let Some(gamma) = mode.gamma()
+            {
+                assert_eq!(mode.apply(0.3), 0.3_f64.powf(gamma));
+            }
+        }
+
+        assert_eq!(accumulated_alpha(0, 0.1), 0.0);
+        assert!((accumulated_alpha(1, 0.1) - 0.1).abs() < 1e-12);
+        assert!((accumulated_alpha(2, 0.1) - 0.19).abs() < 1e-12);
+        assert_eq!(accumulated_alpha(5, 1.0), 1.0);
+        assert!(accumulated_alpha(1000, 0.01) < 1.0);
+    }
+
+    #[test]
+    fn two_color_gradient()
+    {
+        let colormap = Colormap::Gradient(Color::RGB(0, 0, 0), Color::RGB(255, 136, 0));
+
+        assert_eq!(colormap.color(0), Color::RGB(0, 0, 0));
+        assert_eq!(colormap.color(255), Color::RGB(255, 136, 0));
+        assert_eq!(colormap.color(51), Color::RGB(51, 27, 0));
+    }
+
+    #[test]
+    fn matches_dividing_by_top()
+    {
+        // multiplying by 1 / top instead rounds some values down a step (25 with a top of 200 / 7)
+        for top in [3.0, 49.0 / 3.0, 200.0 / 7.0, 1000.0]
+        {
+            let pass = ColorPass::new(top, 1.0, Colormap::Gray);
+
+            for value in 0..2000
+            {
+                let expected = ((value as f64 / top) * 256.0).clamp(0.0, 255.0) as u8;
+
+                assert_eq!(pass.intensity(value), expected, "top: {top}, value: {value}");
+            }
+        }
+    }
+
+    #[test]
+    fn zero_top()
+    {
+        let pass = ColorPass::new(0.0, 1.0, Colormap::Gray);
+
+        let counts = Image::from_data(2, 1, vec![0, 5]);
+        let scalar: Vec<Color> = counts.data().iter().map(|&value| pass.color(value)).collect();
+
+        assert_eq!(pass.apply(&counts).data(), &scalar[..]);
+    }
+}
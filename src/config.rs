@@ -0,0 +1,1024 @@
+use std::{
+    fs,
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+    path::{Path, PathBuf}
+};
+
+use sdl2::pixels::Color;
+
+use crate::{
+    image::Image,
+    input::{Selection, HeaderSkip},
+    counts::load_counts,
+    export::{load_png, BackgroundBlend},
+    render::{VisualizationMode, Coloring, ChannelSource, RecordStat, SplitPoint},
+    color::{Colormap, ScaleMode},
+    disasm::Arch,
+    layout::{Layout, Orientation},
+    overlay::{Corner, Highlight, HIGHLIGHT_COLOR}
+};
+
+
+// images wider or taller than this cant be made into a texture by a lot of sdl renderers
+const MAX_IMAGE_SIDE: usize = 8192;
+
+// the first argument can pick something other than visualizing the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command
+{
+    Visualize,
+    Benchmark,
+    // compares the render to a baseline image and exits with an error if they differ
+    Check
+}
+
+impl Command
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("benchmark", Self::Benchmark),
+        ("check", Self::Check)
+    ];
+}
+
+#[derive(Clone)]
+pub struct Config
+{
+    pub command: Command,
+    pub input: String,
+    // every input, only batch mode (--output-dir) takes more than one
+    pub inputs: Vec<String>,
+    pub selection: Selection,
+    pub header: HeaderSkip,
+    // where the visualized bytes start in the file, filled in once the selection is resolved
+    pub file_offset: u64,
+    pub max_memory: Option<u64>,
+    pub check_baseline: Option<PathBuf>,
+    // none unless given, so it can be told apart from an explicit 0 outside of check
+    pub tolerance: Option<usize>,
+    pub mode: VisualizationMode,
+    pub size: usize,
+    pub scale: u32,
+    pub colormap: Colormap,
+    pub width: Option<usize>,
+    pub line_color: Color,
+    pub scale_mode: ScaleMode,
+    pub accumulate: bool,
+    pub contours: Option<usize>,
+    pub saturation: f64,
+    pub coloring: Coloring,
+    pub hue_source: ChannelSource,
+    pub brightness_source: ChannelSource,
+    pub orientation: Orientation,
+    pub class_legend: bool,
+    pub legend_corner: Corner,
+    pub mirror: bool,
+    pub entropy_window: Option<usize>,
+    pub entropy_layout: Layout,
+    pub fft_size: usize,
+    pub selfdiff_layout: Layout,
+    pub hamming_layout: Layout,
+    pub lag: Option<usize>,
+    // none unless given so it can be told apart from the default outside of split mode
+    pub split_point: Option<SplitPoint>,
+    pub min_string: usize,
+    pub record_size: Option<usize>,
+    pub record_stat: RecordStat,
+    pub arch: Arch,
+    pub debug_curve: bool,
+    pub ruler: bool,
+    pub ruler_marks: usize,
+    pub highlights: Vec<Highlight>,
+    pub follow: bool,
+    pub animate: bool,
+    pub animate_steps: usize,
+    pub animate_window: Option<usize>,
+    pub keep_aspect: bool,
+    pub render_driver: Option<String>,
+    pub verbose: bool,
+    pub heat_trail: bool,
+    pub live_histogram: bool,
+    pub histogram_corner: Corner,
+    pub histogram_width: usize,
+    pub histogram_height: usize,
+    pub output: Option<PathBuf>,
+    pub force: bool,
+    pub crop_to_content: bool,
+    pub embed_metadata: bool,
+    // shared since the config gets cloned for every batch entry and render thread
+    pub background: Option<Arc<Image<Color>>>,
+    pub background_blend: BackgroundBlend,
+    pub output_dir: Option<PathBuf>,
+    pub html: Option<PathBuf>,
+    pub compute_only: bool,
+    pub dump_counts: Option<PathBuf>,
+    pub baseline: Option<Image<u32>>
+}
+
+impl Default for Config
+{
+    fn default() -> Self
+    {
+        Self{
+            command: Command::Visualize,
+            input: String::new(),
+            inputs: Vec::new(),
+            selection: Selection::default(),
+            header: HeaderSkip::None,
+            file_offset: 0,
+            max_memory: None,
+            check_baseline: None,
+            tolerance: None,
+            mode: VisualizationMode::Digraph,
+            size: 256,
+            scale: 2,
+            colormap: Colormap::Gray,
+            width: None,
+            line_color: Color::RGB(80, 255, 120),
+            scale_mode: ScaleMode::Linear,
+            accumulate: false,
+            contours: None,
+            saturation: 0.1,
+            coloring: Coloring::Value,
+            hue_source: ChannelSource::Value,
+            brightness_source: ChannelSource::Density,
+            orientation: Orientation::R0,
+            class_legend: false,
+            legend_corner: Corner::BottomRight,
+            mirror: false,
+            entropy_window: None,
+            entropy_layout: Layout::RowMajor,
+            fft_size: 256,
+            selfdiff_layout: Layout::Hilbert,
+            hamming_layout: Layout::Hilbert,
+            lag: None,
+            split_point: None,
+            min_string: 4,
+            record_size: None,
+            record_stat: RecordStat::Entropy,
+            arch: Arch::X64,
+            debug_curve: false,
+            ruler: false,
+            ruler_marks: 5,
+            highlights: Vec::new(),
+            follow: false,
+            animate: false,
+            animate_steps: 300,
+            animate_window: None,
+            keep_aspect: true,
+            render_driver: None,
+            verbose: false,
+            heat_trail: false,
+            live_histogram: false,
+            histogram_corner: Corner::TopRight,
+            histogram_width: 128,
+            histogram_height: 40,
+            output: None,
+            force: false,
+            crop_to_content: false,
+            embed_metadata: true,
+            background: None,
+            background_blend: BackgroundBlend::Under,
+            output_dir: None,
+            html: None,
+            compute_only: false,
+            dump_counts: None,
+            baseline: None
+        }
+    }
+}
+
+impl Config
+{
+    pub fn parse(args: impl IntoIterator<Item=String>) -> Result<Self, String>
+    {
+        let mut config = Self::default();
+
+        let mut inputs = Vec::new();
+
+        let mut offset = None;
+        let mut length = None;
+        let mut slice = None;
+        let mut skip_header = None;
+        let mut auto_skip_header = false;
+
+        let mut args = args.into_iter().skip(1).peekable();
+
+        let command = args.peek().and_then(|arg|
+        {
+            Command::NAMES.iter().find(|(name, _)| name == arg).map(|(_, command)| *command)
+        });
+
+        if let Some(command) = command
+        {
+            config.command = command;
+            args.next();
+        }
+        while let Some(arg) = args.next()
+        {
+            let Some(flag) = arg.strip_prefix("--")
+            else
+            {
+                inputs.push(arg);
+
+                continue;
+            };
+
+            let (name, inline_value) = match flag.split_once('=')
+            {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (flag, None)
+            };
+
+            let mut value = ||
+            {
+                inline_value.clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("--{name} needs a value"))
+            };
+
+            match name
+            {
+                "mode" => config.mode = parse_named(name, &value()?, VisualizationMode::NAMES)?,
+                "size" => config.size = parse_number(name, &value()?)?,
+                "scale" => config.scale = parse_number(name, &value()?)?,
+                "colormap" => config.colormap = parse_named(name, &value()?, Colormap::NAMES)?,
+                "gradient" => config.colormap = parse_gradient(name, &value()?)?,
+                "scale-mode" => config.scale_mode = parse_named(name, &value()?, ScaleMode::NAMES)?,
+                "accumulate" => config.accumulate = parse_flag(name, inline_value.as_deref())?,
+                "contours" => config.contours = Some(parse_number(name, &value()?)?),
+                "saturation" => config.saturation = parse_number(name, &value()?)?,
+                "width" => config.width = Some(parse_number(name, &value()?)?),
+                "line-color" => config.line_color = parse_color(name, &value()?)?,
+                "coloring" => config.coloring = parse_named(name, &value()?, Coloring::NAMES)?,
+                "hue-source" => config.hue_source = parse_named(name, &value()?, ChannelSource::NAMES)?,
+                "brightness-source" =>
+                {
+                    config.brightness_source = parse_named(name, &value()?, ChannelSource::NAMES)?;
+                },
+                "orientation" => config.orientation = parse_named(name, &value()?, Orientation::NAMES)?,
+                "class-legend" => config.class_legend = parse_flag(name, inline_value.as_deref())?,
+                "legend-corner" => config.legend_corner = parse_named(name, &value()?, Corner::NAMES)?,
+                "mirror" => config.mirror = parse_flag(name, inline_value.as_deref())?,
+                "debug-curve" => config.debug_curve = parse_flag(name, inline_value.as_deref())?,
+                "ruler" => config.ruler = parse_flag(name, inline_value.as_deref())?,
+                "ruler-marks" => config.ruler_marks = parse_number(name, &value()?)?,
+                "highlight" => config.highlights.push(parse_highlight(name, &value()?)?),
+                "highlight-file" => config.highlights.extend(load_highlights(&value()?)?),
+                "follow" => config.follow = parse_flag(name, inline_value.as_deref())?,
+                "offset" => offset = Some(parse_number(name, &value()?)?),
+                "length" => length = Some(parse_number(name, &value()?)?),
+                "slice" => slice = Some(parse_slice(name, &value()?)?),
+                "skip-header" => skip_header = Some(parse_number(name, &value()?)?),
+                "max-memory" => config.max_memory = Some(parse_memory(name, &value()?)?),
+                "auto-skip-header" => auto_skip_header = parse_flag(name, inline_value.as_deref())?,
+                "output" => config.output = Some(PathBuf::from(value()?)),
+                "output-dir" => config.output_dir = Some(PathBuf::from(value()?)),
+                "html" => config.html = Some(PathBuf::from(value()?)),
+                "embed-metadata" => config.embed_metadata = parse_flag(name, inline_value.as_deref())?,
+                "crop-to-content" => config.crop_to_content = parse_flag(name, inline_value.as_deref())?,
+                "background-image" => config.background = Some(Arc::new(load_background(&value()?)?)),
+                "background-blend" =>
+                {
+                    config.background_blend = parse_named(name, &value()?, BackgroundBlend::NAMES)?;
+                },
+                "force" => config.force = parse_flag(name, inline_value.as_deref())?,
+                "compute-only" => config.compute_only = parse_flag(name, inline_value.as_deref())?,
+                "dump-counts" => config.dump_counts = Some(PathBuf::from(value()?)),
+                // check compares against an image so --baseline is that image there instead of digraph counts
+                "baseline-image" => config.check_baseline = Some(PathBuf::from(value()?)),
+                "baseline" if config.command == Command::Check => config.check_baseline = Some(PathBuf::from(value()?)),
+                "tolerance" => config.tolerance = Some(parse_number(name, &value()?)?),
+                "baseline" => config.baseline = Some(load_baseline(&value()?)?),
+                "animate" => config.animate = parse_flag(name, inline_value.as_deref())?,
+                "animate-steps" => config.animate_steps = parse_number(name, &value()?)?,
+                "animate-window" => config.animate_window = Some(parse_number(name, &value()?)?),
+                "keep-aspect" => config.keep_aspect = parse_flag(name, inline_value.as_deref())?,
+                "render-driver" => config.render_driver = Some(value()?),
+                "verbose" => config.verbose = parse_flag(name, inline_value.as_deref())?,
+                "live-histogram" => config.live_histogram = parse_flag(name, inline_value.as_deref())?,
+                "histogram-corner" => config.histogram_corner = parse_named(name, &value()?, Corner::NAMES)?,
+                "histogram-width" => config.histogram_width = parse_number(name, &value()?)?,
+                "histogram-height" => config.histogram_height = parse_number(name, &value()?)?,
+                "heat-trail" => config.heat_trail = parse_flag(name, inline_value.as_deref())?,
+                "entropy-window" => config.entropy_window = Some(parse_number(name, &value()?)?),
+                "entropy-layout" => config.entropy_layout = parse_named(name, &value()?, Layout::NAMES)?,
+                "fft-size" => config.fft_size = parse_number(name, &value()?)?,
+                "selfdiff-layout" => config.selfdiff_layout = parse_named(name, &value()?, Layout::NAMES)?,
+                "hamming-layout" => config.hamming_layout = parse_named(name, &value()?, Layout::NAMES)?,
+                "arch" => config.arch = parse_named(name, &value()?, Arch::NAMES)?,
+                "record-size" => config.record_size = Some(parse_number(name, &value()?)?),
+                "record-stat" => config.record_stat = parse_named(name, &value()?, RecordStat::NAMES)?,
+                "lag" => config.lag = Some(parse_number(name, &value()?)?),
+                "min-string" => config.min_string = parse_number(name, &value()?)?,
+                "split-point" => config.split_point = Some(parse_split_point(name, &value()?)?),
+                _ => return Err(format!("unknown option --{name}"))
+            }
+        }
+
+        config.selection = match (slice, offset, length)
+        {
+            (Some(_), Some(_), _) | (Some(_), _, Some(_)) =>
+            {
+                return Err("--slice cant be combined with --offset or --length".to_owned());
+            },
+            (Some(selection), _, _) => selection,
+            (None, offset, length) => Selection::Bytes{offset: offset.unwrap_or(0), length}
+        };
+
+        config.header = match (skip_header, auto_skip_header)
+        {
+            (Some(_), true) => return Err("--skip-header cant be combined with --auto-skip-header".to_owned()),
+            (Some(amount), false) => HeaderSkip::Bytes(amount),
+            (None, true) => HeaderSkip::Auto,
+            (None, false) => HeaderSkip::None
+        };
+
+        if config.header != HeaderSkip::None && config.selection.needs_length()
+        {
+            return Err("--slice cant be combined with --skip-header or --auto-skip-header".to_owned());
+        }
+
+        // a percentage of a file that keeps growing would have to move as it grows
+        if config.follow && config.selection.needs_length()
+        {
+            return Err("--slice cant be combined with --follow".to_owned());
+        }
+
+        // benchmarks make up their own input if theres none
+        config.input = match inputs.first()
+        {
+            Some(input) => input.clone(),
+            None if config.command == Command::Benchmark => String::new(),
+            None => return Err("provide input file plz".to_owned())
+        };
+
+        if inputs.len() > 1 && config.output_dir.is_none()
+        {
+            return Err("only one input file can be visualized (unless using --output-dir)".to_owned());
+        }
+
+        config.inputs = inputs;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // how the current mode places its cells, none if it has its own fixed shape
+    pub fn mode_layout(&self) -> Option<Layout>
+    {
+        match self.mode
+        {
+            VisualizationMode::Hilbert | VisualizationMode::Opcodes => Some(Layout::Hilbert),
+            VisualizationMode::Entropy => Some(self.entropy_layout),
+            VisualizationMode::SelfDiff => Some(self.selfdiff_layout),
+            VisualizationMode::Hamming => Some(self.hamming_layout),
+            _ => None
+        }
+    }
+
+    // benchmark checks the config of every mode with this too
+    pub fn validate(&self) -> Result<(), String>
+    {
+        if self.size == 0
+        {
+            return Err("--size cant be 0".to_owned());
+        }
+
+        if self.scale == 0
+        {
+            return Err("--scale cant be 0".to_owned());
+        }
+
+        if self.size > MAX_IMAGE_SIDE
+        {
+            return Err(format!("--size cant be more than {MAX_IMAGE_SIDE} (got {})", self.size));
+        }
+
+        if let Some(width) = self.width.filter(|&width| width > MAX_IMAGE_SIDE)
+        {
+            return Err(format!("--width cant be more than {MAX_IMAGE_SIDE} (got {width})"));
+        }
+
+        // the spectrogram is half as tall as the fft size
+        if self.fft_size / 2 > MAX_IMAGE_SIDE
+        {
+            return Err(format!("--fft-size cant be more than {} (got {})", MAX_IMAGE_SIDE * 2, self.fft_size));
+        }
+
+        if self.mode_layout() == Some(Layout::Hilbert) && !self.size.is_power_of_two()
+        {
+            return Err(format!("--size must be a power of 2 for the hilbert layout (got {})", self.size));
+        }
+
+        if self.fft_size < 2 || !self.fft_size.is_multiple_of(2)
+        {
+            return Err(format!("--fft-size must be even and at least 2 (got {})", self.fft_size));
+        }
+
+        if !(self.saturation > 0.0 && self.saturation <= 1.0)
+        {
+            return Err(format!("--saturation must be above 0 and at most 1 (got {})", self.saturation));
+        }
+
+        if self.ruler && self.mode != VisualizationMode::Hilbert
+        {
+            return Err("--ruler only works with --mode hilbert".to_owned());
+        }
+
+        if !self.highlights.is_empty() && self.mode != VisualizationMode::Hilbert
+        {
+            return Err("--highlight and --highlight-file only work with --mode hilbert".to_owned());
+        }
+
+        if self.split_point.is_some() && self.mode != VisualizationMode::Split
+        {
+            return Err("--split-point only works with --mode split".to_owned());
+        }
+
+        if self.ruler_marks < 2
+        {
+            return Err(format!("--ruler-marks needs at least 2 marks for the start and end (got {})", self.ruler_marks));
+        }
+
+        if self.contours == Some(0)
+        {
+            return Err("--contours needs at least 1 level".to_owned());
+        }
+
+        if self.contours.is_some() && (self.mode != VisualizationMode::Digraph || self.accumulate)
+        {
+            return Err("--contours only works with the plain digraph (--mode digraph without --accumulate)".to_owned());
+        }
+
+        if self.class_legend && (self.mode != VisualizationMode::Hilbert || self.coloring != Coloring::Class)
+        {
+            return Err("--class-legend only makes sense with --mode hilbert --coloring class".to_owned());
+        }
+
+        // check renders like exporting does so it takes the same options
+        let exported = self.output.is_some() || self.output_dir.is_some() || self.command == Command::Check;
+
+        if self.crop_to_content && !exported
+        {
+            return Err("--crop-to-content only applies to exported images (--output, --output-dir or check)".to_owned());
+        }
+
+        if self.background.is_some() && !exported
+        {
+            return Err("--background-image only applies to exported images (--output, --output-dir or check)".to_owned());
+        }
+
+        // batch mode writes its own files for every input so these would just get ignored
+        if self.output_dir.is_some() && self.output.is_some()
+        {
+            return Err("--output cant be combined with --output-dir".to_owned());
+        }
+
+        if self.output_dir.is_some() && self.dump_counts.is_some()
+        {
+            return Err("--dump-counts cant be combined with --output-dir".to_owned());
+        }
+
+        if self.html.is_some() && self.output_dir.is_none()
+        {
+            return Err("--html needs --output-dir for the images it links to".to_owned());
+        }
+
+        if self.command == Command::Check && self.check_baseline.is_none()
+        {
+            return Err("check needs a --baseline-image to compare to".to_owned());
+        }
+
+        if self.command != Command::Check && self.check_baseline.is_some()
+        {
+            return Err("--baseline-image only works with check".to_owned());
+        }
+
+        if self.command != Command::Check && self.tolerance.is_some()
+        {
+            return Err("--tolerance only works with check".to_owned());
+        }
+
+        if self.min_string == 0
+        {
+            return Err("--min-string cant be 0".to_owned());
+        }
+
+        if self.max_memory == Some(0)
+        {
+            return Err("--max-memory cant be 0".to_owned());
+        }
+
+        // following keeps every byte that shows up so theres nothing to cap
+        if self.max_memory.is_some() && self.follow
+        {
+            return Err("--max-memory cant be combined with --follow".to_owned());
+        }
+
+        if self.animate_steps == 0
+        {
+            return Err("--animate-steps cant be 0".to_owned());
+        }
+
+        if self.width == Some(0)
+        {
+            return Err("--width cant be 0".to_owned());
+        }
+
+        if self.animate_window == Some(0)
+        {
+            return Err("--animate-window cant be 0".to_owned());
+        }
+
+        if self.mode == VisualizationMode::Records && self.record_size.is_none()
+        {
+            return Err("--mode records needs --record-size".to_owned());
+        }
+
+        if self.record_size == Some(0)
+        {
+            return Err("--record-size cant be 0".to_owned());
+        }
+
+        if self.lag == Some(0)
+        {
+            return Err("--lag cant be 0".to_owned());
+        }
+
+        if self.entropy_window == Some(0)
+        {
+            return Err("--entropy-window cant be 0".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+fn load_background(path: &str) -> Result<Image<Color>, String>
+{
+    let background = load_png(Path::new(path)).map_err(|err| format!("cant load background {path}: {err}"))?;
+
+    if background.width() == 0 || background.height() == 0
+    {
+        return Err(format!("background {path} is empty"));
+    }
+
+    Ok(background)
+}
+
+fn load_baseline(path: &str) -> Result<Image<u32>, String>
+{
+    let baseline = load_counts(Path::new(path)).map_err(|err|
+    {
+        format!("cant load baseline {path}: {err}")
+    })?;
+
+    if (baseline.width(), baseline.height()) != (256, 256)
+    {
+        return Err(format!(
+            "baseline {path} must be a 256x256 count matrix (got {}x{})",
+            baseline.width(),
+            baseline.height()
+        ));
+    }
+
+    Ok(baseline)
+}
+
+// decimal or hex with a 0x in front
+fn parse_offset(value: &str) -> Option<usize>
+{
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok()
+    }
+}
+
+fn parse_range(start: &str, end: &str) -> Result<(usize, usize), String>
+{
+    let start = parse_offset(start).ok_or_else(|| format!("{start} isnt an offset"))?;
+    let end = parse_offset(end).ok_or_else(|| format!("{end} isnt an offset"))?;
+
+    if start >= end
+    {
+        return Err(format!("the start must be before the end (got {start:#X} to {end:#X})"));
+    }
+
+    Ok((start, end))
+}
+
+// START:END or START:END:RRGGBB
+fn parse_highlight(name: &str, value: &str) -> Result<Highlight, String>
+{
+    let invalid = |reason: &str| format!("invalid --{name} value: {value} ({reason})");
+
+    let mut parts = value.splitn(3, ':');
+
+    let (Some(start), Some(end)) = (parts.next(), parts.next())
+    else
+    {
+        return Err(invalid("expected START:END"));
+    };
+
+    let (start, end) = parse_range(start, end).map_err(|err| invalid(&err))?;
+
+    let color = parts.next().map(|color| parse_color(name, color)).transpose()?.unwrap_or(HIGHLIGHT_COLOR);
+
+    Ok(Highlight{start, end, color, label: None})
+}
+
+// one range per line as START END [#RRGGBB] [LABEL], blank lines and lines starting with # are skipped
+fn parse_highlights(text: &str) -> Result<Vec<Highlight>, String>
+{
+    text.lines().enumerate().filter_map(|(index, line)|
+    {
+        let line = line.trim();
+
+        (!line.is_empty() && !line.starts_with('#')).then_some((index + 1, line))
+    }).map(|(number, line)|
+    {
+        let invalid = |reason: &str| format!("line {number}: {reason}");
+
+        let mut parts = line.splitn(3, char::is_whitespace);
+
+        let (Some(start), Some(end)) = (parts.next(), parts.next())
+        else
+        {
+            return Err(invalid("expected START END [#RRGGBB] [LABEL]"));
+        };
+
+        let (start, end) = parse_range(start, end).map_err(|err| invalid(&err))?;
+
+        let rest = parts.next().unwrap_or("").trim_start();
+
+        // the color needs the # here so labels that happen to be hex dont get taken as colors
+        let (color, label) = match rest.strip_prefix('#')
+        {
+            Some(rest) =>
+            {
+                let (color, label) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+                let color = parse_color("highlight-file", color).map_err(|err| invalid(&err))?;
+
+                (color, label.trim())
+            },
+            None => (HIGHLIGHT_COLOR, rest)
+        };
+
+        let label = (!label.is_empty()).then(|| label.to_owned());
+
+        Ok(Highlight{start, end, color, label})
+    }).collect()
+}
+
+fn load_highlights(path: &str) -> Result<Vec<Highlight>, String>
+{
+    let text = fs::read_to_string(path).map_err(|err| format!("cant load highlights {path}: {err}"))?;
+
+    parse_highlights(&text).map_err(|err| format!("bad highlights in {path}, {err}"))
+}
+
+// START%:END% with the percent signs being optional
+fn parse_slice(name: &str, value: &str) -> Result<Selection, String>
+{
+    let invalid = |reason: &str| format!("invalid --{name} value: {value} ({reason})");
+
+    let (start, end) = value.split_once(':').ok_or_else(|| invalid("expected START%:END%"))?;
+
+    let percent = |text: &str|
+    {
+        let text = text.trim();
+        text.strip_suffix('%').unwrap_or(text).parse::<f64>().map_err(|err| invalid(&err.to_string()))
+    };
+
+    let (start, end) = (percent(start)?, percent(end)?);
+
+    if !(0.0 <= start && start < end && end <= 100.0)
+    {
+        return Err(invalid("must be 0 <= START < END <= 100"));
+    }
+
+    Ok(Selection::Percent{start, end})
+}
+
+// bytes with an optional K, M or G (powers of 1024) at the end
+fn parse_memory(name: &str, value: &str) -> Result<u64, String>
+{
+    let (number, unit) = match value.char_indices().last()
+    {
+        Some((index, 'K' | 'k')) => (&value[..index], 1 << 10),
+        Some((index, 'M' | 'm')) => (&value[..index], 1 << 20),
+        Some((index, 'G' | 'g')) => (&value[..index], 1 << 30),
+        _ => (value, 1)
+    };
+
+    let amount: u64 = parse_number(name, number)?;
+
+    amount.checked_mul(unit).ok_or_else(|| format!("invalid --{name} value: {value} (too big)"))
+}
+
+// a percentage ending with % or a byte offset
+fn parse_split_point(name: &str, value: &str) -> Result<SplitPoint, String>
+{
+    match value.strip_suffix('%')
+    {
+        Some(percent) =>
+        {
+            let percent: f64 = parse_number(name, percent)?;
+
+            if !(0.0..=100.0).contains(&percent)
+            {
+                return Err(format!("invalid --{name} value: {value} (must be between 0% and 100%)"));
+            }
+
+            Ok(SplitPoint::Percent(percent))
+        },
+        None => parse_offset(value).map(SplitPoint::Offset).ok_or_else(||
+        {
+            format!("invalid --{name} value: {value} (expected a percentage like 50% or a byte offset)")
+        })
+    }
+}
+
+// RRGGBB hex, optionally starting with a #
+fn parse_color(name: &str, value: &str) -> Result<Color, String>
+{
+    let hex = value.strip_prefix('#').unwrap_or(value);
+
+    let invalid = || format!("invalid --{name} value: {value} (expected a RRGGBB hex color)");
+
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(invalid());
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid());
+
+    Ok(Color::RGB(channel(0)?, channel(1)?, channel(2)?))
+}
+
+// levenshtein distance with swapping two neighbouring chars also counting as one edit, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize
+{
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut before_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 0..a.len()
+    {
+        let mut current = vec![i + 1; b.len() + 1];
+
+        for j in 0..b.len()
+        {
+            let substitution = previous[j] + usize::from(a[i] != b[j]);
+
+            let mut distance = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j]
+            {
+                distance = distance.min(before_previous[j - 1] + 1);
+            }
+
+            current[j + 1] = distance;
+        }
+
+        before_previous = previous;
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// the closest option if its close enough to probably be a typo of it
+fn suggestion<'a>(value: &str, options: impl Iterator<Item=&'a str>) -> Option<&'a str>
+{
+    let value = value.to_lowercase();
+    let allowed = (value.chars().count() / 3).max(1);
+
+    options.map(|option| (edit_distance(&value, option), option))
+        .filter(|&(distance, _)| distance <= allowed)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, option)| option)
+}
+
+// FROM:TO with both being colors like parse_color takes
+fn parse_gradient(name: &str, value: &str) -> Result<Colormap, String>
+{
+    let (from, to) = value.split_once(':').ok_or_else(||
+    {
+        format!("invalid --{name} value: {value} (expected two colors like #000000:#ff8800)")
+    })?;
+
+    Ok(Colormap::Gradient(parse_color(name, from)?, parse_color(name, to)?))
+}
+
+fn parse_named<T: Copy>(name: &str, value: &str, options: &[(&str, T)]) -> Result<T, String>
+{
+    options.iter().find(|(option, _)| *option == value).map(|(_, x)| *x).ok_or_else(||
+    {
+        let valid = options.iter().map(|(option, _)| *option).collect::<Vec<_>>().join(", ");
+
+        match suggestion(value, options.iter().map(|(option, _)| *option))
+        {
+            Some(suggested) => format!("invalid --{name} value: {value}, did you mean {suggested}? (valid: {valid})"),
+            None => format!("invalid --{name} value: {value} (valid: {valid})")
+        }
+    })
+}
+
+fn parse_number<T>(name: &str, value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display
+{
+    value.parse().map_err(|err| format!("invalid --{name} value: {value} ({err})"))
+}
+
+fn parse_flag(name: &str, value: Option<&str>) -> Result<bool, String>
+{
+    match value
+    {
+        None | Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        Some(value) => Err(format!("invalid --{name} value: {value} (expected true or false)"))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, String>
+    {
+        Config::parse(["binvis"].iter().chain(args).map(|x| x.to_string()))
+    }
+
+    #[test]
+    fn values_and_flags()
+    {
+        let config = parse(&["--mode", "hilbert", "file", "--orientation=270", "--mirror"]).unwrap();
+
+        assert_eq!(config.input, "file");
+        assert_eq!(config.mode, VisualizationMode::Hilbert);
+        assert_eq!(config.orientation, Orientation::R270);
+        assert!(config.mirror);
+
+        assert!(!parse(&["file", "--mirror=false"]).unwrap().mirror);
+
+        let selection = parse(&["file", "--offset", "16", "--length=32"]).unwrap().selection;
+        assert_eq!(selection, Selection::Bytes{offset: 16, length: Some(32)});
+
+        assert_eq!(parse(&["file", "--line-color", "#ff8000"]).unwrap().line_color, Color::RGB(255, 128, 0));
+        assert_eq!(parse(&["file", "--line-color=00ff7f"]).unwrap().line_color, Color::RGB(0, 255, 127));
+
+        let gradient = parse(&["file", "--gradient", "#000000:ff8800"]).unwrap().colormap;
+        assert_eq!(gradient, Colormap::Gradient(Color::RGB(0, 0, 0), Color::RGB(255, 136, 0)));
+
+        let highlights = parse(&["file", "--mode", "hilbert", "--highlight", "0x10:32", "--highlight", "0:1:ff0000"]).unwrap().highlights;
+        assert_eq!(highlights, vec![
+            Highlight{start: 16, end: 32, color: HIGHLIGHT_COLOR, label: None},
+            Highlight{start: 0, end: 1, color: Color::RGB(255, 0, 0), label: None}
+        ]);
+
+        let config = parse(&["file", "--offset", "16", "--skip-header", "64"]).unwrap();
+        assert_eq!((config.selection, config.header), (Selection::Bytes{offset: 16, length: None}, HeaderSkip::Bytes(64)));
+        assert_eq!(parse(&["file", "--auto-skip-header"]).unwrap().header, HeaderSkip::Auto);
+
+        assert_eq!(parse(&["file", "--max-memory", "512M"]).unwrap().max_memory, Some(512 << 20));
+        assert_eq!(parse(&["file", "--max-memory", "1000"]).unwrap().max_memory, Some(1000));
+
+        let split_point = |value: &str| parse(&["file", "--mode", "split", "--split-point", value]).map(|x| x.split_point);
+
+        assert_eq!(split_point("25%").unwrap(), Some(SplitPoint::Percent(25.0)));
+        assert_eq!(split_point("0x100").unwrap(), Some(SplitPoint::Offset(256)));
+        assert!(parse(&["file", "--split-point", "25%"]).is_err());
+
+        let selection = parse(&["file", "--slice", "45%:55"]).unwrap().selection;
+        assert_eq!(selection, Selection::Percent{start: 45.0, end: 55.0});
+    }
+
+    #[test]
+    fn highlight_file_lines()
+    {
+        let text = "# segments\n0x0 0x400 .text\n\n0x400 0x800 #00ff00 .data section\n2048 4096\n";
+
+        assert_eq!(parse_highlights(text).unwrap(), vec![
+            Highlight{start: 0, end: 0x400, color: HIGHLIGHT_COLOR, label: Some(".text".to_owned())},
+            Highlight{start: 0x400, end: 0x800, color: Color::RGB(0, 255, 0), label: Some(".data section".to_owned())},
+            Highlight{start: 2048, end: 4096, color: HIGHLIGHT_COLOR, label: None}
+        ]);
+
+        let err = parse_highlights("0 10\n10 5 label\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+
+        assert!(parse_highlights("0 10 #12345 label").is_err());
+        assert!(parse_highlights("0").is_err());
+    }
+
+    #[test]
+    fn suggests_close_names()
+    {
+        assert_eq!(edit_distance("viridus", "viridis"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("magam", "magma"), 1);
+
+        let err = parse(&["file", "--colormap", "viridus"]).err().unwrap();
+        assert!(err.contains("did you mean viridis?"), "{err}");
+
+        let err = parse(&["file", "--mode", "Hilbrt"]).err().unwrap();
+        assert!(err.contains("did you mean hilbert?"), "{err}");
+
+        let config = parse(&["file", "--mode", "hamming", "--hamming-layout", "rowmajor"]).unwrap();
+        assert_eq!((config.mode_layout(), config.selfdiff_layout), (Some(Layout::RowMajor), Layout::Hilbert));
+
+        let err = parse(&["file", "--selfdiff-layout", "zigzag"]).err().unwrap();
+        assert!(!err.contains("did you mean"), "{err}");
+        assert!(err.contains("rowmajor, hilbert"), "{err}");
+    }
+
+    #[test]
+    fn commands()
+    {
+        let config = parse(&["benchmark"]).unwrap();
+        assert_eq!(config.command, Command::Benchmark);
+        assert!(config.input.is_empty());
+
+        let config = parse(&["benchmark", "file", "--size", "128"]).unwrap();
+        assert_eq!((config.command, config.input.as_str(), config.size), (Command::Benchmark, "file", 128));
+
+        let config = parse(&["check", "file", "--baseline-image", "old.png", "--tolerance", "10"]).unwrap();
+        assert_eq!(config.command, Command::Check);
+        assert_eq!((config.check_baseline, config.baseline.is_none()), (Some(PathBuf::from("old.png")), true));
+        assert_eq!(config.tolerance, Some(10));
+
+        assert!(parse(&["check", "file", "--baseline-image", "old.png", "--crop-to-content"]).unwrap().crop_to_content);
+
+        // the image doesnt get loaded as counts
+        let config = parse(&["check", "file", "--baseline", "img.png"]).unwrap();
+        assert_eq!((config.check_baseline, config.baseline.is_none()), (Some(PathBuf::from("img.png")), true));
+
+        assert!(parse(&["check", "file"]).is_err());
+        assert!(parse(&["check", "--baseline-image", "old.png"]).is_err());
+        assert!(parse(&["file", "--tolerance", "10"]).is_err());
+        assert!(parse(&["file", "--baseline-image", "old.png"]).is_err());
+
+        // only the first argument is a command
+        assert_eq!(parse(&["file", "benchmark", "--output-dir", "out"]).unwrap().command, Command::Visualize);
+    }
+
+    #[test]
+    fn rejects_bad_input()
+    {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["a", "b"]).is_err());
+        assert_eq!(parse(&["a", "b", "--output-dir", "out"]).unwrap().inputs, ["a", "b"]);
+        assert!(parse(&["a", "--html", "index.html"]).is_err());
+        assert!(parse(&["file", "--orientation", "45"]).is_err());
+        assert!(parse(&["file", "--slice", "50%:50%"]).is_err());
+        assert!(parse(&["file", "--line-color", "fff"]).is_err());
+        assert!(parse(&["file", "--gradient", "#000000"]).is_err());
+        assert!(parse(&["file", "--split-point", "150%"]).is_err());
+        assert!(parse(&["file", "--max-memory", "0"]).is_err());
+        assert!(parse(&["file", "--max-memory", "1G", "--follow"]).is_err());
+        assert!(parse(&["file", "--slice", "10%:20%", "--follow"]).is_err());
+        assert!(parse(&["a", "b", "--output-dir", "out", "--dump-counts", "counts.csv"]).is_err());
+        assert!(parse(&["a", "b", "--output-dir", "out", "--output", "a.png"]).is_err());
+        assert!(parse(&["file", "--mode", "strings", "--min-string", "0"]).is_err());
+        assert!(parse(&["file", "--max-memory", "12T"]).is_err());
+        assert!(parse(&["file", "--split-point", "half"]).is_err());
+        assert!(parse(&["file", "--skip-header", "8", "--auto-skip-header"]).is_err());
+        assert!(parse(&["file", "--slice", "10%:20%", "--auto-skip-header"]).is_err());
+        assert!(parse(&["file", "--highlight", "0:10"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--highlight", "10:5"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--highlight", "10"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--highlight-file", "/nonexistent/ranges"]).is_err());
+        assert!(parse(&["file", "--gradient", "#000000:#ff88"]).is_err());
+        assert!(parse(&["file", "--line-color", "gg0000"]).is_err());
+        assert!(parse(&["file", "--accumulate", "--saturation", "0"]).is_err());
+        assert!(parse(&["file", "--accumulate", "--saturation", "1.5"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--class-legend"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--coloring", "class", "--class-legend"]).is_ok());
+        assert!(parse(&["file", "--slice", "-10%:20%"]).is_err());
+        assert!(parse(&["file", "--slice", "10%:101%"]).is_err());
+        assert!(parse(&["file", "--slice", "10%"]).is_err());
+        assert!(parse(&["file", "--slice", "10%:20%", "--offset", "5"]).is_err());
+        assert!(parse(&["file", "--mode", "hilbert", "--size", "300"]).is_err());
+        assert!(parse(&["file", "--size"]).is_err());
+        assert!(parse(&["file", "--size", "0"]).is_err());
+        assert!(parse(&["file", "--scale", "0"]).is_err());
+        assert!(parse(&["file", "--size", "8192"]).is_ok());
+        assert!(parse(&["file", "--size", "16384"]).is_err());
+        assert!(parse(&["file", "--mode", "plot", "--width", "100000"]).is_err());
+        assert!(parse(&["file", "--mode", "spectrogram", "--fft-size", "16384"]).is_ok());
+        assert!(parse(&["file", "--mode", "spectrogram", "--fft-size", "16386"]).is_err());
+        assert!(parse(&["file", "--mode", "entropy", "--size", "300"]).is_ok());
+        assert!(parse(&["file", "--mode", "entropy", "--entropy-layout", "hilbert", "--size", "300"]).is_err());
+    }
+}
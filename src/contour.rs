@@ -0,0 +1,149 @@
+use crate::image::{Image, Pos2};
+
+
+// log of the counts blurred a bit, raw counts are too noisy to get contours that mean anything
+pub fn density_field(counts: &Image<u32>) -> Image<f64>
+{
+    let (width, height) = (counts.width(), counts.height());
+
+    let logs = counts.clone().map(|count| (count as f64).ln_1p());
+
+    let data = (0..width * height).map(|index|
+    {
+        let pos = Image::<f64>::index_to_pos_assoc(width, index);
+
+        let (mut sum, mut amount) = (0.0, 0);
+        for y in pos.y.saturating_sub(1)..(pos.y + 2).min(height)
+        {
+            for x in pos.x.saturating_sub(1)..(pos.x + 2).min(width)
+            {
+                sum += logs[Pos2{x, y}];
+                amount += 1;
+            }
+        }
+
+        sum / amount as f64
+    }).collect();
+
+    Image::from_data(width, height, data)
+}
+
+// evenly spaced quantiles of the nonempty part of the field, lowest first
+pub fn levels(field: &Image<f64>, amount: usize) -> Vec<f64>
+{
+    let mut values: Vec<f64> = field.data().iter().copied().filter(|&x| x > 0.0).collect();
+    if values.is_empty()
+    {
+        return Vec::new();
+    }
+
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mut levels: Vec<f64> = (1..=amount).map(|i|
+    {
+        values[(values.len() - 1) * i / (amount + 1)]
+    }).collect();
+
+    // a field with few distinct values would repeat levels and draw the same line multiple times
+    levels.dedup();
+
+    levels
+}
+
+// marching squares over the cell centers, each segment being two points in cell coordinates
+pub fn segments(field: &Image<f64>, level: f64) -> Vec<(Pos2<f64>, Pos2<f64>)>
+{
+    let mut segments = Vec::new();
+
+    for y in 0..field.height().saturating_sub(1)
+    {
+        for x in 0..field.width().saturating_sub(1)
+        {
+            let corner = |dx: usize, dy: usize|
+            {
+                let pos = Pos2{x: x + dx, y: y + dy};
+
+                (Pos2{x: pos.x as f64, y: pos.y as f64}, field[pos])
+            };
+
+            // going around the square, each edge goes from one corner to the next
+            let corners = [corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1)];
+
+            let crossings: Vec<Pos2<f64>> = (0..4).filter_map(|edge|
+            {
+                let (a, a_value) = corners[edge];
+                let (b, b_value) = corners[(edge + 1) % 4];
+
+                if (a_value >= level) == (b_value >= level)
+                {
+                    return None;
+                }
+
+                let t = (level - a_value) / (b_value - a_value);
+
+                Some(Pos2{x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t})
+            }).collect();
+
+            match crossings[..]
+            {
+                [a, b] => segments.push((a, b)),
+                [a, b, c, d] =>
+                {
+                    // a saddle, the average decides whether the middle is inside which picks the pairing
+                    let middle = corners.iter().map(|(_, value)| value).sum::<f64>() / 4.0;
+
+                    if (middle >= level) == (corners[0].1 >= level)
+                    {
+                        segments.push((a, b));
+                        segments.push((c, d));
+                    }
+                    else
+                    {
+                        segments.push((a, d));
+                        segments.push((b, c));
+                    }
+                },
+                _ => ()
+            }
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn circle_contour()
+    {
+        let size = 21;
+        let data = (0..size * size).map(|index|
+        {
+            let (x, y) = ((index % size) as f64 - 10.0, (index / size) as f64 - 10.0);
+
+            10.0 - (x * x + y * y).sqrt()
+        }).collect();
+
+        let field = Image::from_data(size, size, data);
+
+        let segments = segments(&field, 5.0);
+        assert!(segments.len() > 20);
+
+        // every point lands on the circle with radius 5 (give or take the interpolation being linear)
+        assert!(segments.iter().flat_map(|&(a, b)| [a, b]).all(|pos|
+        {
+            let radius = ((pos.x - 10.0).powi(2) + (pos.y - 10.0).powi(2)).sqrt();
+
+            (radius - 5.0).abs() < 0.2
+        }));
+
+        let levels = levels(&field, 3);
+        assert_eq!(levels.len(), 3);
+        assert!(levels.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert!(super::levels(&Image::new(4, 4, 0.0), 3).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    fs,
+    io,
+    path::Path
+};
+
+use crate::image::Image;
+
+
+// one row per line, values separated by commas
+pub fn save_counts(path: &Path, counts: &Image<u32>) -> io::Result<()>
+{
+    let text: String = counts.data().chunks(counts.width()).map(|row|
+    {
+        let mut line = row.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+        line.push('\n');
+
+        line
+    }).collect();
+
+    fs::write(path, text)
+}
+
+pub fn load_counts(path: &Path) -> io::Result<Image<u32>>
+{
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let text = fs::read_to_string(path)?;
+
+    let rows = text.lines().filter(|line| !line.trim().is_empty()).enumerate().map(|(y, line)|
+    {
+        line.split(',').map(|value|
+        {
+            value.trim().parse::<u32>().map_err(|err|
+            {
+                invalid(format!("line {}: invalid count {value:?} ({err})", y + 1))
+            })
+        }).collect::<io::Result<Vec<u32>>>()
+    }).collect::<io::Result<Vec<_>>>()?;
+
+    let width = rows.first().map(|row| row.len()).unwrap_or(0);
+    if let Some(y) = rows.iter().position(|row| row.len() != width)
+    {
+        return Err(invalid(format!("line {} has a different amount of values than the first", y + 1)));
+    }
+
+    let height = rows.len();
+
+    Ok(Image::from_data(width, height, rows.concat()))
+}
+
+// log2 of how much more likely each cell is than in the baseline
+// both get a pseudocount of 1 per cell so empty cells dont divide by zero
+pub fn log_ratios(counts: &Image<u32>, baseline: &Image<u32>) -> Image<f64>
+{
+    assert_eq!((counts.width(), counts.height()), (baseline.width(), baseline.height()));
+
+    let cells = counts.data().len() as f64;
+
+    let total = |image: &Image<u32>| image.data().iter().map(|&x| x as f64).sum::<f64>() + cells;
+
+    let counts_total = total(counts);
+    let baseline_total = total(baseline);
+
+    let data = counts.data().iter().zip(baseline.data()).map(|(&count, &base)|
+    {
+        let p = (count as f64 + 1.0) / counts_total;
+        let q = (base as f64 + 1.0) / baseline_total;
+
+        (p / q).log2()
+    }).collect();
+
+    Image::from_data(counts.width(), counts.height(), data)
+}
+
+// pointwise mutual information of each pair, log2(p(x, y) / (p(x) * p(y))) with x being the first byte
+// (the column) and y the second (the row), positive when a pair shows up more than the bytes
+// being independent would explain and negative when less. pairs that never show up would be -infinity
+// so theyre none instead, bytes that never show up only ever have zero counts so they dont divide by zero
+pub fn pmi(counts: &Image<u32>) -> Image<Option<f64>>
+{
+    let (width, height) = (counts.width(), counts.height());
+
+    let mut firsts = vec![0_u64; width];
+    let mut seconds = vec![0_u64; height];
+
+    let mut total = 0_u64;
+    counts.data().iter().enumerate().for_each(|(index, &count)|
+    {
+        firsts[index % width] += count as u64;
+        seconds[index / width] += count as u64;
+
+        total += count as u64;
+    });
+
+    let data = counts.data().iter().enumerate().map(|(index, &count)|
+    {
+        (count != 0).then(||
+        {
+            let expected = firsts[index % width] as f64 * seconds[index / width] as f64 / total as f64;
+
+            (count as f64 / expected).log2()
+        })
+    }).collect();
+
+    Image::from_data(width, height, data)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn save_load_roundtrip()
+    {
+        let path = env::temp_dir().join(format!("binvis_counts_test_{}.csv", process::id()));
+
+        let counts = Image::from_data(3, 2, vec![0, 1, 2, 300, 4_000_000, 5]);
+        save_counts(&path, &counts).unwrap();
+
+        let loaded = load_counts(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((loaded.width(), loaded.height()), (3, 2));
+        assert_eq!(loaded.data(), counts.data());
+    }
+
+    #[test]
+    fn mutual_information()
+    {
+        // independent bytes have 0 everywhere
+        let independent = pmi(&Image::from_data(2, 2, vec![1, 3, 2, 6]));
+        assert!(independent.data().iter().all(|x| x.unwrap().abs() < 1e-12));
+
+        // 0 always followed by 1 and 1 by 0
+        let alternating = pmi(&Image::from_data(2, 2, vec![0, 5, 5, 0]));
+        assert_eq!(alternating.data(), &[None, Some(1.0), Some(1.0), None]);
+    }
+
+    #[test]
+    fn ratios()
+    {
+        let counts = Image::from_data(2, 1, vec![9, 1]);
+        let same = log_ratios(&counts, &counts);
+
+        assert!(same.data().iter().all(|x| x.abs() < 1e-12));
+
+        let baseline = Image::from_data(2, 1, vec![1, 9]);
+        let ratios = log_ratios(&counts, &baseline);
+
+        assert!(ratios.data()[0] > 0.0);
+        assert!(ratios.data()[1] < 0.0);
+    }
+}
@@ -0,0 +1,124 @@
+use iced_x86::{Decoder, DecoderOptions};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch
+{
+    X86,
+    X64
+}
+
+impl Arch
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("x86", Self::X86),
+        ("x64", Self::X64)
+    ];
+
+    fn bitness(&self) -> u32
+    {
+        match self
+        {
+            Self::X86 => 32,
+            Self::X64 => 64
+        }
+    }
+}
+
+// what a linear sweep disassembly made of each byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary
+{
+    // start of an instruction this many bytes long
+    Start(u8),
+    Inside,
+    Invalid
+}
+
+// decodes from the start, skipping a single byte whenever something doesnt decode
+// x86 resynchronizes quickly so code regions still end up mostly on the real boundaries
+pub fn boundaries(bytes: &[u8], arch: Arch) -> Vec<Boundary>
+{
+    let mut boundaries = vec![Boundary::Invalid; bytes.len()];
+
+    let mut decoder = Decoder::new(arch.bitness(), bytes, DecoderOptions::NONE);
+
+    let mut position = 0;
+    while position < bytes.len()
+    {
+        decoder.set_position(position).expect("position is inside the bytes");
+
+        let instruction = decoder.decode();
+        if instruction.is_invalid()
+        {
+            position += 1;
+            continue;
+        }
+
+        let len = instruction.len();
+
+        boundaries[position] = Boundary::Start(len as u8);
+        boundaries[position + 1..position + len].fill(Boundary::Inside);
+
+        position += len;
+    }
+
+    boundaries
+}
+
+// how much of the chunk decoded and the average length of the instructions starting in it
+pub fn chunk_summary(boundaries: &[Boundary]) -> (f64, f64)
+{
+    if boundaries.is_empty()
+    {
+        return (0.0, 0.0);
+    }
+
+    let invalid = boundaries.iter().filter(|&&x| x == Boundary::Invalid).count();
+
+    let (starts, total_len) = boundaries.iter().fold((0, 0), |(starts, total), x|
+    {
+        match x
+        {
+            Boundary::Start(len) => (starts + 1, total + *len as usize),
+            _ => (starts, total)
+        }
+    });
+
+    let valid = 1.0 - invalid as f64 / boundaries.len() as f64;
+    let average_len = if starts == 0 { 0.0 } else { total_len as f64 / starts as f64 };
+
+    (valid, average_len)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn sweeps_instructions()
+    {
+        // push rbp, mov rbp, rsp, then a byte that doesnt decode in 64 bit mode, then ret
+        let bytes = [0x55, 0x48, 0x89, 0xe5, 0x06, 0xc3];
+
+        assert_eq!(boundaries(&bytes, Arch::X64), [
+            Boundary::Start(1),
+            Boundary::Start(3),
+            Boundary::Inside,
+            Boundary::Inside,
+            Boundary::Invalid,
+            Boundary::Start(1)
+        ]);
+
+        // push es is fine in 32 bit mode
+        assert_eq!(boundaries(&[0x06], Arch::X86), [Boundary::Start(1)]);
+
+        // cut off in the middle of an instruction
+        assert_eq!(boundaries(&[0x48, 0x89], Arch::X64), [Boundary::Invalid, Boundary::Invalid]);
+
+        let (valid, average_len) = chunk_summary(&boundaries(&bytes, Arch::X64));
+        assert!((valid - 5.0 / 6.0).abs() < 1e-12);
+        assert!((average_len - 5.0 / 3.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::stats::histogram;
+
+
+// entropies are computed from a fixed point sum of c * log2(c) over the histogram
+// so the incremental version can add and remove terms without any float drift
+// (u128 since a whole file of tens of GB being one byte value would be past u64)
+const TERM_SCALE: f64 = (1_u64 << 24) as f64;
+
+fn term(count: u64) -> u128
+{
+    if count < 2
+    {
+        return 0;
+    }
+
+    let count = count as f64;
+
+    (count * count.log2() * TERM_SCALE).round() as u128
+}
+
+fn entropy_from_sum(sum: u128, total: u64) -> f64
+{
+    if total == 0
+    {
+        return 0.0;
+    }
+
+    let total_f = total as f64;
+
+    (total_f.log2() - sum as f64 / TERM_SCALE / total_f).max(0.0)
+}
+
+// shannon entropy in bits per byte (0 to 8)
+pub fn entropy(bytes: &[u8]) -> f64
+{
+    histogram_entropy(&histogram(bytes))
+}
+
+pub fn histogram_entropy(counts: &[u64; 256]) -> f64
+{
+    let sum = counts.iter().map(|&count| term(count)).sum();
+
+    entropy_from_sum(sum, counts.iter().sum())
+}
+
+fn windows(len: usize, window: usize, step: usize) -> impl Iterator<Item=(usize, usize)>
+{
+    (0..len).step_by(step.max(1)).map(move |start| (start, (start + window).min(len)))
+}
+
+pub fn sliding_entropy_naive(bytes: &[u8], window: usize, step: usize) -> Vec<f64>
+{
+    windows(bytes.len(), window, step).map(|(start, end)| entropy(&bytes[start..end])).collect()
+}
+
+struct Histogram
+{
+    counts: [u64; 256],
+    sum: u128,
+    total: u64
+}
+
+impl Histogram
+{
+    fn new() -> Self
+    {
+        Self{counts: [0; 256], sum: 0, total: 0}
+    }
+
+    fn add(&mut self, byte: u8)
+    {
+        let count = &mut self.counts[byte as usize];
+
+        self.sum -= term(*count);
+        *count += 1;
+        self.sum += term(*count);
+
+        self.total += 1;
+    }
+
+    fn remove(&mut self, byte: u8)
+    {
+        let count = &mut self.counts[byte as usize];
+
+        self.sum -= term(*count);
+        *count -= 1;
+        self.sum += term(*count);
+
+        self.total -= 1;
+    }
+
+    fn entropy(&self) -> f64
+    {
+        entropy_from_sum(self.sum, self.total)
+    }
+}
+
+// same result as sliding_entropy_naive but only touches the bytes entering and leaving the window
+pub fn sliding_entropy(bytes: &[u8], window: usize, step: usize) -> Vec<f64>
+{
+    let mut histogram = Histogram::new();
+
+    let mut current = (0, 0);
+
+    windows(bytes.len(), window, step).map(|(start, end)|
+    {
+        let (previous_start, previous_end) = current;
+
+        if start < previous_end
+        {
+            bytes[previous_start..start].iter().for_each(|&x| histogram.remove(x));
+            bytes[previous_end..end].iter().for_each(|&x| histogram.add(x));
+        }
+        else
+        {
+            histogram = Histogram::new();
+            bytes[start..end].iter().for_each(|&x| histogram.add(x));
+        }
+
+        current = (start, end);
+
+        histogram.entropy()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn test_bytes() -> Vec<u8>
+    {
+        let mut state: u32 = 7;
+
+        (0..5000).map(|i|
+        {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+
+            // mix of noisy and very repetitive regions
+            if (i / 700) % 2 == 0
+            {
+                (state >> 16) as u8
+            }
+            else
+            {
+                (i % 3) as u8
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn incremental_matches_naive()
+    {
+        let bytes = test_bytes();
+
+        for (window, step) in [(64, 64), (256, 16), (100, 1), (10, 37), (1, 1), (10000, 500)]
+        {
+            assert_eq!(
+                sliding_entropy(&bytes, window, step),
+                sliding_entropy_naive(&bytes, window, step),
+                "window: {window}, step: {step}"
+            );
+        }
+    }
+
+    #[test]
+    fn known_entropies()
+    {
+        assert_eq!(entropy(&[]), 0.0);
+        assert_eq!(entropy(&[5; 100]), 0.0);
+
+        let all: Vec<u8> = (0..=255).collect();
+        assert!((entropy(&all) - 8.0).abs() < 1e-6);
+
+        assert!((entropy(&[0, 1, 0, 1]) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn huge_counts()
+    {
+        // about 32GiB of the same byte, the sum of terms doesnt fit in a u64 anymore
+        let mut counts = [0; 256];
+        counts[0] = (1 << 35) + 12345;
+        assert!(histogram_entropy(&counts) < 1e-6);
+
+        counts[1] = counts[0];
+        assert!((histogram_entropy(&counts) - 1.0).abs() < 1e-6);
+    }
+}
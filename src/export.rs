@@ -0,0 +1,425 @@
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, BufReader},
+    path::{Path, PathBuf}
+};
+
+use sdl2::pixels::Color;
+
+use crate::{
+    config::Config,
+    counts::save_counts,
+    image::{Image, Pos2},
+    render::VisualizationMode,
+    budget::{self, Loaded}
+};
+
+
+// everything in here has to work without sdl ever getting initialized
+pub fn export(config: &Config, output: &Path) -> io::Result<()>
+{
+    check_not_input(config, output)?;
+
+    let config = &budget::located(Path::new(&config.input), config)?;
+
+    let input = budget::load(Path::new(&config.input), config)?;
+
+    dump_counts(config, || input.digraph_counts())?;
+
+    save_png_with_text(output, &render_export(&input, config), &metadata(config, input.len()))
+}
+
+// where the image came from, empty if --embed-metadata=false
+pub fn metadata(config: &Config, bytes: usize) -> Vec<(String, String)>
+{
+    if !config.embed_metadata
+    {
+        return Vec::new();
+    }
+
+    let mode = VisualizationMode::NAMES.iter().find(|(_, mode)| *mode == config.mode).map(|(name, _)| *name);
+
+    [
+        ("Software", format!("binvis {}", env!("CARGO_PKG_VERSION"))),
+        ("Source", config.input.clone()),
+        ("Bytes", bytes.to_string()),
+        ("Mode", mode.unwrap_or("unknown").to_owned())
+    ].into_iter().map(|(key, value)| (key.to_owned(), value)).collect()
+}
+
+// pixels of empty space left around the content when cropping
+const CROP_MARGIN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundBlend
+{
+    Under,
+    Over
+}
+
+impl BackgroundBlend
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("under", Self::Under),
+        ("over", Self::Over)
+    ];
+}
+
+// render with the export only options applied
+pub fn render_export(input: &Loaded, config: &Config) -> Image<Color>
+{
+    let image = input.render(config);
+
+    if !config.crop_to_content && config.background.is_none()
+    {
+        return image;
+    }
+
+    let empty = empty_pixels(&image, input, config);
+
+    let crop = crop_bounds(&empty, config);
+
+    let image = cropped(image, crop);
+
+    match &config.background
+    {
+        Some(background) => composite_background(image, &cropped(empty, crop), background, config.background_blend),
+        None => image
+    }
+}
+
+// which pixels have nothing in them, the digraph knows from its counts since the low end
+// of a colormap doesnt have to be black, other modes leave their empty cells black
+// with --baseline the pairs only the baseline has get drawn too so theyre not empty
+fn empty_pixels(image: &Image<Color>, input: &Loaded, config: &Config) -> Image<bool>
+{
+    let counts = (config.mode == VisualizationMode::Digraph)
+        .then(|| input.digraph_counts())
+        .filter(|counts| (counts.width(), counts.height()) == (image.width(), image.height()));
+
+    match (counts, &config.baseline)
+    {
+        (Some(counts), Some(baseline)) =>
+        {
+            let data = counts.data().iter().zip(baseline.data()).map(|(&count, &base)| count == 0 && base == 0).collect();
+
+            Image::from_data(counts.width(), counts.height(), data)
+        },
+        (Some(counts), None) => counts.map(|count| count == 0),
+        (None, _) => image.clone().map(|color| (color.r, color.g, color.b) == (0, 0, 0))
+    }
+}
+
+// the background gets stretched to the image if its a different size
+fn composite_background(
+    image: Image<Color>,
+    empty: &Image<bool>,
+    background: &Image<Color>,
+    blend: BackgroundBlend
+) -> Image<Color>
+{
+    let background = if (background.width(), background.height()) == (image.width(), image.height())
+    {
+        background.clone()
+    }
+    else
+    {
+        background.resize(image.width(), image.height())
+    };
+
+    match blend
+    {
+        BackgroundBlend::Under =>
+        {
+            // only the empty parts of the visualization let the background show through
+            // so dim cells keep their color instead of fading into it
+            let data = image.data().iter().zip(empty.data()).map(|(&c, &empty)|
+            {
+                Color::RGBA(c.r, c.g, c.b, if empty { 0 } else { c.a })
+            }).collect();
+
+            let mut background = background;
+            background.blend(&Image::from_data(image.width(), image.height(), data));
+
+            background
+        },
+        BackgroundBlend::Over =>
+        {
+            let mut image = image;
+            image.blend(&background);
+
+            image
+        }
+    }
+}
+
+fn cropped<T: Clone>(image: Image<T>, crop: Option<(Pos2<usize>, Pos2<usize>)>) -> Image<T>
+{
+    match crop
+    {
+        Some((pos, size)) => image.crop(pos, size),
+        None => image
+    }
+}
+
+// none if theres nothing to crop
+fn crop_bounds(empty: &Image<bool>, config: &Config) -> Option<(Pos2<usize>, Pos2<usize>)>
+{
+    if !config.crop_to_content
+    {
+        return None;
+    }
+
+    // nothing to crop to, keeping the whole image is less surprising than an empty one
+    let (pos, size) = empty.bounds(|&empty| !empty)?;
+
+    let start = Pos2{x: pos.x.saturating_sub(CROP_MARGIN), y: pos.y.saturating_sub(CROP_MARGIN)};
+    let size = Pos2{x: size.x + (pos.x - start.x) + CROP_MARGIN, y: size.y + (pos.y - start.y) + CROP_MARGIN};
+
+    Some((start, size))
+}
+
+// does nothing if --dump-counts wasnt given, counts only get built if theyre saved
+pub fn dump_counts(config: &Config, counts: impl FnOnce() -> Image<u32>) -> io::Result<()>
+{
+    match &config.dump_counts
+    {
+        Some(path) =>
+        {
+            check_not_input(config, path)?;
+
+            save_counts(path, &counts())
+        },
+        None => Ok(())
+    }
+}
+
+// the output might not exist yet so only its directory can be canonicalized
+fn canonical_output(path: &Path) -> io::Result<PathBuf>
+{
+    if let Ok(path) = path.canonicalize()
+    {
+        return Ok(path);
+    }
+
+    let parent = match path.parent()
+    {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => env::current_dir()?
+    };
+
+    Ok(path.file_name().map(|name| parent.join(name)).unwrap_or(parent))
+}
+
+// writing over the file being visualized would destroy it, --force allows it anyway
+pub fn check_not_input(config: &Config, output: &Path) -> io::Result<()>
+{
+    if config.force
+    {
+        return Ok(());
+    }
+
+    let Ok(input) = Path::new(&config.input).canonicalize()
+    else
+    {
+        return Ok(());
+    };
+
+    if canonical_output(output)? == input
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is the input file, refusing to overwrite it (use --force)", output.display())
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn save_png(path: &Path, image: &Image<Color>) -> io::Result<()>
+{
+    save_png_with_text(path, image, &[])
+}
+
+// text goes into tEXt chunks, or iTXt ones if it doesnt fit in latin-1 (like some file names)
+pub fn save_png_with_text(path: &Path, image: &Image<Color>, text: &[(String, String)]) -> io::Result<()>
+{
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    for (key, value) in text
+    {
+        if value.chars().all(|c| (c as u32) < 256)
+        {
+            encoder.add_text_chunk(key.clone(), value.clone())?;
+        }
+        else
+        {
+            encoder.add_itxt_chunk(key.clone(), value.clone())?;
+        }
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.data_raw())?;
+
+    writer.finish()?;
+
+    Ok(())
+}
+
+pub fn load_png(path: &Path) -> io::Result<Image<Color>>
+{
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let buffer = &buffer[..info.buffer_size()];
+
+    let data = match info.color_type
+    {
+        png::ColorType::Rgba => buffer.chunks_exact(4).map(|c| Color::RGBA(c[0], c[1], c[2], c[3])).collect(),
+        png::ColorType::Rgb => buffer.chunks_exact(3).map(|c| Color::RGB(c[0], c[1], c[2])).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).map(|c| Color::RGBA(c[0], c[0], c[0], c[1])).collect(),
+        png::ColorType::Grayscale => buffer.iter().map(|&c| Color::RGB(c, c, c)).collect(),
+        png::ColorType::Indexed => unreachable!("normalize_to_color8 expands palettes")
+    };
+
+    Ok(Image::from_data(info.width as usize, info.height as usize, data))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{fs, process, sync::Arc};
+
+    use crate::color::Colormap;
+
+    use super::*;
+
+    #[test]
+    fn refuses_overwriting_input()
+    {
+        let path = env::temp_dir().join(format!("binvis_same_path_test_{}", process::id()));
+        fs::write(&path, b"some input bytes").unwrap();
+
+        let path_str = path.to_str().unwrap();
+
+        let config = Config::parse(["binvis", path_str, "--output", path_str].map(String::from)).unwrap();
+
+        let err = export(&config, &path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&path).unwrap(), b"some input bytes");
+
+        // same file through a different path
+        let dotted = path.parent().unwrap().join(".").join(path.file_name().unwrap());
+        assert!(check_not_input(&config, &dotted).is_err());
+
+        let other = path.with_extension("png");
+        assert!(check_not_input(&config, &other).is_ok());
+
+        let forced = Config::parse(["binvis", path_str, "--output", path_str, "--force"].map(String::from)).unwrap();
+        export(&forced, &path).unwrap();
+
+        assert!(load_png(&path).is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn crops_to_content()
+    {
+        let parse = |args: &[&str]|
+        {
+            Config::parse(["binvis", "input", "--output", "out.png"].iter().chain(args).map(|x| x.to_string())).unwrap()
+        };
+
+        // pairs only between 0x10..0x20 and 0x30..0x38
+        let bytes: Vec<u8> = (0..1000_usize).flat_map(|i| [0x10 + (i % 16) as u8, 0x30 + (i % 8) as u8]).collect();
+
+        let bytes = Loaded::Bytes(bytes);
+
+        let cropped = render_export(&bytes, &parse(&["--crop-to-content"]));
+        let full = render_export(&bytes, &parse(&[]));
+
+        assert_eq!((full.width(), full.height()), (256, 256));
+        assert!(cropped.width() < 64 && cropped.height() < 64);
+
+        let empty = render_export(&Loaded::Bytes(Vec::new()), &parse(&["--crop-to-content"]));
+        assert_eq!((empty.width(), empty.height()), (256, 256));
+
+        // pairs only the baseline has are drawn blue so they stay in too
+        let mut baseline = Image::new(256, 256, 0);
+        baseline[Pos2{x: 200, y: 220}] = 5;
+
+        let with_baseline = render_export(&bytes, &Config{baseline: Some(baseline), ..parse(&["--crop-to-content"])});
+        assert!(with_baseline.width() > 180 && with_baseline.height() > 200);
+    }
+
+    #[test]
+    fn background_blends()
+    {
+        let image = Image::from_data(3, 1, vec![Color::RGB(0, 0, 0), Color::RGB(255, 0, 0), Color::RGB(20, 5, 0)]);
+        let empty = Image::from_data(3, 1, vec![true, false, false]);
+
+        // a different size so it gets stretched
+        let background = Image::new(4, 4, Color::RGBA(0, 0, 200, 255));
+
+        let under = composite_background(image.clone(), &empty, &background, BackgroundBlend::Under);
+        assert_eq!(under.data(), &[Color::RGB(0, 0, 200), Color::RGB(255, 0, 0), Color::RGB(20, 5, 0)]);
+
+        let watermark = Image::new(3, 1, Color::RGBA(0, 0, 200, 0));
+        let over = composite_background(image.clone(), &empty, &watermark, BackgroundBlend::Over);
+        assert_eq!(over.data(), image.data());
+
+        // viridis starts at dark purple, the empty digraph cells still show the background
+        let bytes = Loaded::Bytes(vec![1, 2, 3]);
+        let config = Config{
+            colormap: Colormap::Viridis,
+            background: Some(Arc::new(Image::new(256, 256, Color::RGB(0, 0, 200)))),
+            ..Default::default()
+        };
+
+        let under = render_export(&bytes, &config);
+        assert_eq!(under[Pos2{x: 0, y: 0}], Color::RGB(0, 0, 200));
+        assert_ne!(under[Pos2{x: 1, y: 2}], Color::RGB(0, 0, 200));
+    }
+
+    #[test]
+    fn embeds_metadata()
+    {
+        let input = env::temp_dir().join(format!("binvis_metadata_input_{}", process::id()));
+        let output = input.with_extension("png");
+        fs::write(&input, [1, 2, 3, 4, 5]).unwrap();
+
+        let args = ["binvis", input.to_str().unwrap(), "--mode", "hilbert", "--output", output.to_str().unwrap()];
+        let config = Config::parse(args.map(String::from)).unwrap();
+
+        export(&config, &output).unwrap();
+
+        let decoder = png::Decoder::new(BufReader::new(File::open(&output).unwrap()));
+        let reader = decoder.read_info().unwrap();
+
+        let text: Vec<(&str, &str)> = reader.info().uncompressed_latin1_text.iter()
+            .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+            .collect();
+
+        assert!(text.contains(&("Source", input.to_str().unwrap())));
+        assert!(text.contains(&("Bytes", "5")));
+        assert!(text.contains(&("Mode", "hilbert")));
+        assert!(text.iter().any(|(key, value)| *key == "Software" && value.starts_with("binvis ")));
+
+        let without = Config{embed_metadata: false, ..config};
+        assert!(metadata(&without, 5).is_empty());
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+}
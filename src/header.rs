@@ -0,0 +1,260 @@
+// how many bytes from the start of the file get looked at to find the header length
+pub const DETECT_LENGTH: usize = 1 << 16;
+
+type HeaderLength = fn(&[u8]) -> Option<usize>;
+
+// formats with a header that can be told apart by its magic, in the order theyre checked
+const FORMATS: &[(&str, HeaderLength)] = &[
+    ("png", png),
+    ("bmp", bmp),
+    ("wav", wav),
+    ("elf", elf),
+    ("pe", pe),
+    ("gzip", gzip),
+    ("zip", zip)
+];
+
+// the name of the format and how long its header is, none if its not a known format
+// or the header doesnt fit in the bytes
+pub fn detect(bytes: &[u8]) -> Option<(&'static str, usize)>
+{
+    FORMATS.iter().find_map(|(name, length)|
+    {
+        length(bytes).filter(|&length| length <= bytes.len()).map(|length| (*name, length))
+    })
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<usize>
+{
+    bytes.get(at..at + 2).map(|x| u16::from_le_bytes([x[0], x[1]]) as usize)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<usize>
+{
+    bytes.get(at..at + 4).map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]) as usize)
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> Option<usize>
+{
+    bytes.get(at..at + 8).and_then(|x| usize::try_from(u64::from_le_bytes(x.try_into().ok()?)).ok())
+}
+
+// the signature and the IHDR chunk (4 length, 4 type, 13 data, 4 crc)
+fn png(bytes: &[u8]) -> Option<usize>
+{
+    bytes.starts_with(b"\x89PNG\r\n\x1a\n").then_some(8 + 25)
+}
+
+// everything before the pixel data
+fn bmp(bytes: &[u8]) -> Option<usize>
+{
+    if !bytes.starts_with(b"BM")
+    {
+        return None;
+    }
+
+    read_u32(bytes, 10)
+}
+
+// riff chunks up to the start of the samples in the data chunk
+fn wav(bytes: &[u8]) -> Option<usize>
+{
+    if !(bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE"))
+    {
+        return None;
+    }
+
+    let mut chunk = 12;
+    loop
+    {
+        let id = bytes.get(chunk..chunk + 4)?;
+        let length = read_u32(bytes, chunk + 4)?;
+
+        if id == b"data"
+        {
+            return Some(chunk + 8);
+        }
+
+        // chunks are padded to an even length
+        chunk += 8 + length + (length & 1);
+    }
+}
+
+// the elf header and the program headers if they come right after it like they usually do
+fn elf(bytes: &[u8]) -> Option<usize>
+{
+    if !bytes.starts_with(b"\x7fELF")
+    {
+        return None;
+    }
+
+    // only little endian since thats everything binvis is likely to see
+    if bytes.get(5) != Some(&1)
+    {
+        return None;
+    }
+
+    // e_phoff and where e_ehsize is, e_phentsize and e_phnum come right after it
+    let (program_offset, sizes) = match bytes.get(4)
+    {
+        Some(1) => (read_u32(bytes, 0x1c)?, 0x28),
+        Some(2) => (read_u64(bytes, 0x20)?, 0x34),
+        _ => return None
+    };
+
+    let header_size = read_u16(bytes, sizes)?;
+    let entry_size = read_u16(bytes, sizes + 2)?;
+    let entries = read_u16(bytes, sizes + 4)?;
+
+    if program_offset == header_size
+    {
+        Some(header_size + entry_size * entries)
+    }
+    else
+    {
+        Some(header_size)
+    }
+}
+
+// SizeOfHeaders from the optional header, covers the dos stub and the section table
+fn pe(bytes: &[u8]) -> Option<usize>
+{
+    if !bytes.starts_with(b"MZ")
+    {
+        return None;
+    }
+
+    let pe_start = read_u32(bytes, 0x3c)?;
+    if bytes.get(pe_start..pe_start + 4) != Some(b"PE\0\0")
+    {
+        return None;
+    }
+
+    // the signature and the 20 byte coff header come before the optional header
+    read_u32(bytes, pe_start + 4 + 20 + 60)
+}
+
+const GZIP_EXTRA: u8 = 1 << 2;
+const GZIP_NAME: u8 = 1 << 3;
+const GZIP_COMMENT: u8 = 1 << 4;
+const GZIP_HEADER_CRC: u8 = 1 << 1;
+
+// the fixed 10 bytes and whichever optional fields the flags say are there
+fn gzip(bytes: &[u8]) -> Option<usize>
+{
+    if !bytes.starts_with(b"\x1f\x8b")
+    {
+        return None;
+    }
+
+    let flags = *bytes.get(3)?;
+
+    let mut length = 10;
+
+    if flags & GZIP_EXTRA != 0
+    {
+        length += 2 + read_u16(bytes, length)?;
+    }
+
+    let skip_string = |length: &mut usize| -> Option<()>
+    {
+        *length += bytes.get(*length..)?.iter().position(|&x| x == 0)? + 1;
+
+        Some(())
+    };
+
+    if flags & GZIP_NAME != 0
+    {
+        skip_string(&mut length)?;
+    }
+
+    if flags & GZIP_COMMENT != 0
+    {
+        skip_string(&mut length)?;
+    }
+
+    if flags & GZIP_HEADER_CRC != 0
+    {
+        length += 2;
+    }
+
+    Some(length)
+}
+
+// the first local file header, the rest of the archive is file data and more headers
+fn zip(bytes: &[u8]) -> Option<usize>
+{
+    if !bytes.starts_with(b"PK\x03\x04")
+    {
+        return None;
+    }
+
+    Some(30 + read_u16(bytes, 26)? + read_u16(bytes, 28)?)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn detects_headers()
+    {
+        let mut bmp = b"BM".to_vec();
+        bmp.resize(200, 0);
+        bmp[10] = 138;
+        assert_eq!(detect(&bmp), Some(("bmp", 138)));
+
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        wav.extend([0; 16]);
+        wav.extend(b"data\x04\0\0\0\x01\x02\x03\x04");
+        assert_eq!(detect(&wav), Some(("wav", 44)));
+
+        let mut gzip = b"\x1f\x8b\x08\x08\0\0\0\0\0\x03name.txt\0".to_vec();
+        gzip.extend([0xaa; 20]);
+        assert_eq!(detect(&gzip), Some(("gzip", 19)));
+
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.resize(26, 0);
+        zip.extend([4, 0, 2, 0]);
+        zip.extend(b"a.rsxx");
+        assert_eq!(detect(&zip), Some(("zip", 36)));
+
+        // a 64 bit elf with 2 program headers right after the 64 byte header
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(0x200, 0);
+        elf[0x20] = 64;
+        elf[0x34] = 64;
+        elf[0x36] = 56;
+        elf[0x38] = 2;
+        assert_eq!(detect(&elf), Some(("elf", 64 + 2 * 56)));
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x400, 0);
+        pe[0x3c] = 0x80;
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x80 + 24 + 60] = 0x00;
+        pe[0x80 + 24 + 61] = 0x02;
+        assert_eq!(detect(&pe), Some(("pe", 0x200)));
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(100, 0);
+        assert_eq!(detect(&png), Some(("png", 33)));
+    }
+
+    #[test]
+    fn unknown_or_cut_off()
+    {
+        assert_eq!(detect(b""), None);
+        assert_eq!(detect(b"just some text"), None);
+
+        // the header says the data starts past the end of whats there
+        let mut bmp = b"BM".to_vec();
+        bmp.resize(20, 0);
+        bmp[10] = 138;
+        assert_eq!(detect(&bmp), None);
+
+        assert_eq!(detect(b"RIFF\0\0\0\0WAVEfmt \xff\xff\0\0"), None);
+        assert_eq!(detect(b"\x1f\x8b\x08\x08\0\0\0\0\0\x03no end"), None);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::image::Pos2;
+
+
+pub struct HilbertCurve
+{
+    order: usize
+}
+
+impl HilbertCurve
+{
+    pub fn new(size: usize) -> Self
+    {
+        assert!(size.is_power_of_two(), "size must be a power of 2 (got {size})");
+
+        Self{order: size.trailing_zeros() as usize}
+    }
+
+    fn rotate(&self, mut pos: Pos2<usize>, check: Pos2<usize>, value: usize) -> Pos2<usize>
+    {
+        if check.y != 0
+        {
+            return pos;
+        }
+
+        if check.x == 1
+        {
+            pos.x = value - 1 - pos.x;
+            pos.y = value - 1 - pos.y;
+        }
+
+        Pos2{x: pos.y, y: pos.x}
+    }
+
+    #[allow(dead_code)]
+    pub fn point_to_value(&self, mut pos: Pos2<usize>) -> usize
+    {
+        let n = 2_usize.pow(self.order as u32);
+
+        (0..self.order).rev().map(|s|
+        {
+            let s = 2_usize.pow(s as u32);
+
+            let rx = ((pos.x & s) > 0) as usize;
+            let ry = ((pos.y & s) > 0) as usize;
+
+            pos = self.rotate(pos, Pos2{x: rx, y: ry}, n);
+
+            s * s * ((3 * rx) ^ ry)
+        }).sum()
+    }
+
+    pub fn value_to_point(&self, mut value: usize) -> Pos2<usize>
+    {
+        let mut pos = Pos2{x: 0, y: 0};
+
+        for s in 0..self.order
+        {
+            let s = 2_usize.pow(s as u32);
+
+            let rx = (value / 2) & 1;
+            let ry = (value ^ rx) & 1;
+
+            pos = self.rotate(pos, Pos2{x: rx, y: ry}, s);
+
+            pos.x += s * rx;
+            pos.y += s * ry;
+
+            value /= 4;
+        }
+
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn inverse_hilbert()
+    {
+        let n = 512;
+
+        let curve = HilbertCurve::new(n);
+
+        let total = n * n;
+        for i in 0..total
+        {
+            let point = curve.value_to_point(i);
+
+            assert_eq!(curve.point_to_value(point), i);
+        }
+    }
+}
@@ -0,0 +1,643 @@
+use std::ops::{Index, IndexMut};
+
+use sdl2::pixels::Color;
+
+use crate::hilbert::HilbertCurve;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image<T=Color>
+{
+    data: Vec<T>,
+    width: usize,
+    height: usize
+}
+
+impl<T> Image<T>
+where
+    T: Clone
+{
+    pub fn new(width: usize, height: usize, c: T) -> Self
+    {
+        Self{
+            data: vec![c; width * height],
+            width,
+            height
+        }
+    }
+
+    pub fn map<F, U>(self, f: F) -> Image<U>
+    where
+        F: FnMut(T) -> U
+    {
+        Image{
+            data: self.data.into_iter().map(f).collect(),
+            width: self.width,
+            height: self.height
+        }
+    }
+
+    pub fn unhilbertify(&mut self)
+    {
+        assert_eq!(self.width, self.height);
+
+        let size = self.width;
+        let curve = HilbertCurve::new(size);
+
+        self.remap_positions(|index|
+        {
+            let pos = curve.value_to_point(index);
+
+            Self::to_index_assoc(size, pos)
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn hilbertify(&mut self)
+    {
+        assert_eq!(self.width, self.height);
+
+        let size = self.width;
+        let curve = HilbertCurve::new(size);
+
+        self.remap_positions(|index|
+        {
+            let pos = Self::index_to_pos_assoc(size, index);
+
+            curve.point_to_value(pos)
+        });
+    }
+
+    // clamped to the image
+    pub fn fill_rect(&mut self, pos: Pos2<usize>, size: Pos2<usize>, c: T)
+    {
+        let end_x = (pos.x + size.x).min(self.width);
+        let end_y = (pos.y + size.y).min(self.height);
+
+        for y in pos.y.min(end_y)..end_y
+        {
+            let row = y * self.width;
+
+            self.data[row + pos.x.min(end_x)..row + end_x].fill(c.clone());
+        }
+    }
+
+    // parts of src that dont fit get cut off
+    pub fn blit(&mut self, src: &Image<T>, at: Pos2<usize>)
+    {
+        if at.x >= self.width || at.y >= self.height
+        {
+            return;
+        }
+
+        let width = src.width.min(self.width - at.x);
+        let height = src.height.min(self.height - at.y);
+
+        for y in 0..height
+        {
+            let src_start = y * src.width;
+            let start = (at.y + y) * self.width + at.x;
+
+            self.data[start..start + width].clone_from_slice(&src.data[src_start..src_start + width]);
+        }
+    }
+
+    // bresenham, both ends included and points outside the image are skipped
+    pub fn line(&mut self, from: Pos2<usize>, to: Pos2<usize>, c: T)
+    {
+        let (x1, y1) = (to.x as i64, to.y as i64);
+        let (mut x, mut y) = (from.x as i64, from.y as i64);
+
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+
+        let step_x = if x < x1 { 1 } else { -1 };
+        let step_y = if y < y1 { 1 } else { -1 };
+
+        let mut error = dx + dy;
+        loop
+        {
+            if (x as usize) < self.width && (y as usize) < self.height
+            {
+                self[Pos2{x: x as usize, y: y as usize}] = c.clone();
+            }
+
+            if x == x1 && y == y1
+            {
+                return;
+            }
+
+            let doubled = error * 2;
+
+            if doubled >= dy
+            {
+                error += dy;
+                x += step_x;
+            }
+
+            if doubled <= dx
+            {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // the part of the image inside the rectangle, clamped to the image
+    pub fn crop(&self, pos: Pos2<usize>, size: Pos2<usize>) -> Self
+    {
+        let start_x = pos.x.min(self.width);
+        let start_y = pos.y.min(self.height);
+
+        let width = size.x.min(self.width - start_x);
+        let height = size.y.min(self.height - start_y);
+
+        let data = (start_y..start_y + height).flat_map(|y|
+        {
+            let start = y * self.width + start_x;
+
+            self.data[start..start + width].iter().cloned()
+        }).collect();
+
+        Self{data, width, height}
+    }
+
+    // smallest rectangle (position and size) containing every value that matches, none if nothing does
+    pub fn bounds(&self, predicate: impl Fn(&T) -> bool) -> Option<(Pos2<usize>, Pos2<usize>)>
+    {
+        let mut low = Pos2{x: usize::MAX, y: usize::MAX};
+        let mut high = Pos2{x: 0, y: 0};
+
+        self.data.iter().enumerate().filter(|(_, value)| predicate(value)).for_each(|(index, _)|
+        {
+            let pos = Self::index_to_pos_assoc(self.width, index);
+
+            low = Pos2{x: low.x.min(pos.x), y: low.y.min(pos.y)};
+            high = Pos2{x: high.x.max(pos.x), y: high.y.max(pos.y)};
+        });
+
+        (low.x <= high.x).then(|| (low, Pos2{x: high.x - low.x + 1, y: high.y - low.y + 1}))
+    }
+
+    // clockwise
+    pub fn rotate_90(&self) -> Self
+    {
+        let width = self.height;
+        let height = self.width;
+
+        let data = (0..width * height).map(|index|
+        {
+            let pos = Self::index_to_pos_assoc(width, index);
+
+            self[Pos2{x: pos.y, y: self.height - 1 - pos.x}].clone()
+        }).collect();
+
+        Self{data, width, height}
+    }
+
+    pub fn flip_horizontal(&mut self)
+    {
+        self.data.chunks_mut(self.width).for_each(|row| row.reverse());
+    }
+
+    pub fn flip_vertical(&mut self)
+    {
+        let width = self.width;
+
+        let mut rows: Vec<_> = self.data.chunks(width).map(|row| row.to_vec()).collect();
+        rows.reverse();
+
+        self.data = rows.concat();
+    }
+
+    fn remap_positions(&mut self, mut f: impl FnMut(usize) -> usize)
+    {
+        let mut output = self.data.clone();
+
+        self.data.iter().enumerate().for_each(|(i, value)|
+        {
+            let new_position = f(i);
+
+            output[new_position] = value.clone();
+        });
+
+        self.data = output;
+    }
+
+    pub fn width(&self) -> usize
+    {
+        self.width
+    }
+
+    pub fn height(&self) -> usize
+    {
+        self.height
+    }
+
+    pub fn data(&self) -> &[T]
+    {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [T]
+    {
+        &mut self.data
+    }
+
+    pub fn to_index(&self, pos: Pos2<usize>) -> usize
+    {
+        Self::to_index_assoc(self.width, pos)
+    }
+
+    pub fn to_index_assoc(width: usize, pos: Pos2<usize>) -> usize
+    {
+        pos.y * width + pos.x
+    }
+
+    pub fn index_to_pos_assoc(width: usize, index: usize) -> Pos2<usize>
+    {
+        Pos2{
+            x: index % width,
+            y: index / width
+        }
+    }
+}
+
+impl<T> Image<T>
+{
+    pub fn from_data(width: usize, height: usize, data: Vec<T>) -> Self
+    {
+        assert_eq!(data.len(), width * height);
+
+        Self{data, width, height}
+    }
+}
+
+impl Image<Color>
+{
+    // bilinear, cell centers are at whole coordinates and positions outside get clamped to the edge
+    pub fn sample(&self, fx: f64, fy: f64) -> Color
+    {
+        let fx = fx.clamp(0.0, (self.width - 1) as f64);
+        let fy = fy.clamp(0.0, (self.height - 1) as f64);
+
+        let x0 = fx.floor() as usize;
+        let y0 = fy.floor() as usize;
+
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let tx = fx - x0 as f64;
+        let ty = fy - y0 as f64;
+
+        let channels = |c: Color| [c.r, c.g, c.b, c.a].map(|x| x as f64);
+
+        let top_left = channels(self[Pos2{x: x0, y: y0}]);
+        let top_right = channels(self[Pos2{x: x1, y: y0}]);
+        let bottom_left = channels(self[Pos2{x: x0, y: y1}]);
+        let bottom_right = channels(self[Pos2{x: x1, y: y1}]);
+
+        let [r, g, b, a] = std::array::from_fn(|i|
+        {
+            let top = top_left[i] + (top_right[i] - top_left[i]) * tx;
+            let bottom = bottom_left[i] + (bottom_right[i] - bottom_left[i]) * tx;
+
+            (top + (bottom - top) * ty).round() as u8
+        });
+
+        Color::RGBA(r, g, b, a)
+    }
+
+    // bilinear, fine for shrinking a bit but it skips over detail when shrinking a lot
+    pub fn resize(&self, width: usize, height: usize) -> Self
+    {
+        let scale_x = self.width as f64 / width as f64;
+        let scale_y = self.height as f64 / height as f64;
+
+        let data = (0..width * height).map(|index|
+        {
+            let pos = Self::index_to_pos_assoc(width, index);
+
+            // centers of the destination pixels in source coordinates
+            let fx = (pos.x as f64 + 0.5) * scale_x - 0.5;
+            let fy = (pos.y as f64 + 0.5) * scale_y - 0.5;
+
+            self.sample(fx, fy)
+        }).collect();
+
+        Self{data, width, height}
+    }
+
+    // draws over on top of this using overs alpha (the porter duff over operator)
+    // done in premultiplied space so a transparent under doesnt leak its color into the result
+    pub fn blend(&mut self, over: &Image<Color>)
+    {
+        assert_eq!((self.width, self.height), (over.width, over.height));
+
+        let over = over.premultiply_alpha();
+
+        let mut blended = self.premultiply_alpha();
+        blended.data.iter_mut().zip(over.data.iter()).for_each(|(under, over)|
+        {
+            // how much of under is left showing through
+            let left = 1.0 - over.a as f64 / 255.0;
+
+            let channel = |under: u8, over: u8|
+            {
+                (over as f64 + under as f64 * left).round().min(255.0) as u8
+            };
+
+            *under = Color::RGBA(
+                channel(under.r, over.r),
+                channel(under.g, over.g),
+                channel(under.b, over.b),
+                channel(under.a, over.a)
+            );
+        });
+
+        *self = blended.unpremultiply_alpha();
+    }
+
+    // images everywhere else are straight alpha (which is what png and the sdl textures use)
+    // these are for anything that needs the colors already scaled by their alpha
+    pub fn premultiply_alpha(&self) -> Self
+    {
+        self.clone().map(|c|
+        {
+            let scale = |x: u8| (x as f64 * c.a as f64 / 255.0).round() as u8;
+
+            Color::RGBA(scale(c.r), scale(c.g), scale(c.b), c.a)
+        })
+    }
+
+    // fully transparent pixels have no color left to recover so they become transparent black
+    pub fn unpremultiply_alpha(&self) -> Self
+    {
+        self.clone().map(|c|
+        {
+            if c.a == 0
+            {
+                return Color::RGBA(0, 0, 0, 0);
+            }
+
+            let scale = |x: u8| (x as f64 * 255.0 / c.a as f64).round().min(255.0) as u8;
+
+            Color::RGBA(scale(c.r), scale(c.g), scale(c.b), c.a)
+        })
+    }
+
+    pub fn data_raw(&self) -> Vec<u8>
+    {
+        self.data.iter().flat_map(|c|
+        {
+            [c.r, c.g, c.b, c.a]
+        }).collect()
+    }
+}
+
+impl<T> Index<Pos2<usize>> for Image<T>
+where
+    T: Clone
+{
+    type Output = T;
+
+    fn index(&self, index: Pos2<usize>) -> &Self::Output
+    {
+        &self.data[self.to_index(index)]
+    }
+}
+
+impl<T> IndexMut<Pos2<usize>> for Image<T>
+where
+    T: Clone
+{
+    fn index_mut(&mut self, index: Pos2<usize>) -> &mut Self::Output
+    {
+        let index = self.to_index(index);
+
+        &mut self.data[index]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pos2<T>
+{
+    pub x: T,
+    pub y: T
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn rotate_flip()
+    {
+        // 1 2 3
+        // 4 5 6
+        let image = Image::from_data(3, 2, vec![1, 2, 3, 4, 5, 6]);
+
+        let rotated = image.rotate_90();
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(rotated.data(), &[4, 1, 5, 2, 6, 3]);
+
+        let mut flipped = Image::from_data(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        flipped.flip_horizontal();
+        assert_eq!(flipped.data(), &[3, 2, 1, 6, 5, 4]);
+
+        flipped.flip_vertical();
+        assert_eq!(flipped.data(), &[6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn bilinear_sample()
+    {
+        // 0   100
+        // 200 255
+        let image = Image::from_data(2, 2, vec![
+            Color::RGB(0, 0, 0),
+            Color::RGB(100, 0, 0),
+            Color::RGB(200, 0, 0),
+            Color::RGB(255, 0, 0)
+        ]);
+
+        let red = |fx, fy| image.sample(fx, fy).r;
+
+        assert_eq!(red(0.0, 0.0), 0);
+        assert_eq!(red(1.0, 1.0), 255);
+        assert_eq!(red(0.5, 0.0), 50);
+        assert_eq!(red(0.0, 0.5), 100);
+        // (0 + 100 + 200 + 255) / 4 = 138.75
+        assert_eq!(red(0.5, 0.5), 139);
+        // top: 0 + 100 * 0.25 = 25, bottom: 200 + 55 * 0.25 = 213.75, 25 + 188.75 * 0.75 = 166.56
+        assert_eq!(red(0.25, 0.75), 167);
+
+        assert_eq!(red(-3.0, -1.0), 0);
+        assert_eq!(red(5.0, 0.5), 178);
+        assert_eq!(image.sample(0.5, 0.5).a, 255);
+    }
+
+    #[test]
+    fn map_keeps_shape_and_order()
+    {
+        let counts: Image<u32> = Image::from_data(3, 2, vec![0, 1, 2, 10, 20, 30]);
+
+        let mapped = counts.map(|x| x * 2 + 1);
+
+        assert_eq!(mapped, Image::from_data(3, 2, vec![1, 3, 5, 21, 41, 61]));
+        assert_eq!((mapped.width(), mapped.height()), (3, 2));
+        assert_eq!(mapped[Pos2{x: 2, y: 0}], 5);
+        assert_eq!(mapped[Pos2{x: 0, y: 1}], 21);
+
+        // same data in a different shape isnt the same image
+        assert_ne!(mapped, Image::from_data(2, 3, vec![1, 3, 5, 21, 41, 61]));
+    }
+
+    #[test]
+    fn resize_keeps_flat_colors()
+    {
+        let color = Color::RGBA(10, 20, 30, 255);
+        let resized = Image::new(7, 3, color).resize(2, 5);
+
+        assert_eq!((resized.width(), resized.height()), (2, 5));
+        assert!(resized.data().iter().all(|&x| x == color));
+
+        let image = Image::from_data(2, 1, vec![Color::RGB(0, 0, 0), Color::RGB(200, 0, 0)]);
+        assert_eq!(image.resize(4, 1).data().iter().map(|c| c.r).collect::<Vec<_>>(), [0, 50, 150, 200]);
+    }
+
+    fn line_points(from: Pos2<usize>, to: Pos2<usize>) -> Vec<(usize, usize)>
+    {
+        let mut image = Image::new(8, 8, false);
+        image.line(from, to, true);
+
+        image.data().iter().enumerate().filter(|(_, &x)| x).map(|(index, _)| (index % 8, index / 8)).collect()
+    }
+
+    #[test]
+    fn bresenham_lines()
+    {
+        assert_eq!(line_points(Pos2{x: 1, y: 2}, Pos2{x: 4, y: 2}), [(1, 2), (2, 2), (3, 2), (4, 2)]);
+        assert_eq!(line_points(Pos2{x: 3, y: 3}, Pos2{x: 3, y: 3}), [(3, 3)]);
+        assert_eq!(line_points(Pos2{x: 0, y: 0}, Pos2{x: 3, y: 3}), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+
+        // steep and going backwards, one point per row with no gaps
+        let steep = line_points(Pos2{x: 2, y: 7}, Pos2{x: 0, y: 0});
+        assert_eq!(steep.len(), 8);
+        assert!(steep.windows(2).all(|pair| pair[1].1 == pair[0].1 + 1 && pair[0].0.abs_diff(pair[1].0) <= 1));
+        assert_eq!((steep[0], steep[7]), ((0, 0), (2, 7)));
+
+        assert_eq!(line_points(Pos2{x: 0, y: 0}, Pos2{x: 5, y: 2}), line_points(Pos2{x: 5, y: 2}, Pos2{x: 0, y: 0}));
+
+        // clipped at the edge instead of panicking
+        assert_eq!(line_points(Pos2{x: 6, y: 1}, Pos2{x: 10, y: 1}), [(6, 1), (7, 1)]);
+    }
+
+    #[test]
+    fn premultiply_roundtrip()
+    {
+        for a in [0, 1, 7, 64, 128, 200, 254, 255]
+        {
+            let colors: Vec<Color> = (0..=255).step_by(5).map(|c| Color::RGBA(c, 255 - c, c / 2, a)).collect();
+            let image = Image::from_data(colors.len(), 1, colors);
+
+            let premultiplied = image.premultiply_alpha();
+            let roundtrip = premultiplied.unpremultiply_alpha();
+
+            // premultiplying throws away precision, less of it the more opaque the color is
+            let tolerance = if a == 0 { 255 } else { (255 + a as i32 - 1) / a as i32 };
+
+            for ((original, premultiplied), back) in image.data().iter().zip(premultiplied.data()).zip(roundtrip.data())
+            {
+                assert!(premultiplied.r <= original.r && premultiplied.a == a);
+
+                for (x, y) in [(original.r, back.r), (original.g, back.g), (original.b, back.b)]
+                {
+                    assert!((x as i32 - y as i32).abs() <= tolerance, "alpha {a}: {original:?} came back as {back:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blend_over_transparent()
+    {
+        let mut under = Image::from_data(3, 1, vec![
+            Color::RGBA(255, 0, 0, 0),
+            Color::RGBA(0, 0, 255, 255),
+            Color::RGBA(0, 0, 0, 0)
+        ]);
+
+        let over = Image::from_data(3, 1, vec![
+            Color::RGBA(0, 255, 0, 128),
+            Color::RGBA(255, 255, 255, 0),
+            Color::RGBA(0, 0, 0, 0)
+        ]);
+
+        under.blend(&over);
+
+        // the invisible red under doesnt tint the green at all
+        assert_eq!(under.data(), &[Color::RGBA(0, 255, 0, 128), Color::RGBA(0, 0, 255, 255), Color::RGBA(0, 0, 0, 0)]);
+    }
+
+    #[test]
+    fn crop_bounds()
+    {
+        let mut image = Image::new(6, 4, 0);
+        image[Pos2{x: 1, y: 2}] = 5;
+        image[Pos2{x: 4, y: 1}] = 7;
+
+        let (pos, size) = image.bounds(|&x| x != 0).unwrap();
+        assert_eq!((pos, size), (Pos2{x: 1, y: 1}, Pos2{x: 4, y: 2}));
+
+        let cropped = image.crop(pos, size);
+        assert_eq!(cropped.data(), &[0, 0, 0, 7, 5, 0, 0, 0]);
+
+        assert_eq!(image.crop(Pos2{x: 5, y: 3}, Pos2{x: 10, y: 10}).data(), &[0]);
+        assert!(Image::new(3, 3, 0).bounds(|&x| x != 0).is_none());
+    }
+
+    #[test]
+    fn blit_clamps()
+    {
+        let src = Image::from_data(2, 2, vec![1, 2, 3, 4]);
+
+        let mut image = Image::new(3, 3, 0);
+        image.blit(&src, Pos2{x: 0, y: 0});
+        assert_eq!(image.data(), &[1, 2, 0, 3, 4, 0, 0, 0, 0]);
+
+        let mut image = Image::new(3, 3, 0);
+        image.blit(&src, Pos2{x: 1, y: 1});
+        assert_eq!(image.data(), &[0, 0, 0, 0, 1, 2, 0, 3, 4]);
+
+        let mut image = Image::new(3, 3, 0);
+        image.blit(&src, Pos2{x: 2, y: 0});
+        assert_eq!(image.data(), &[0, 0, 1, 0, 0, 3, 0, 0, 0]);
+
+        let mut image = Image::new(3, 3, 0);
+        image.blit(&src, Pos2{x: 0, y: 2});
+        assert_eq!(image.data(), &[0, 0, 0, 0, 0, 0, 1, 2, 0]);
+
+        let mut image = Image::new(3, 3, 0);
+        image.blit(&src, Pos2{x: 3, y: 1});
+        image.blit(&src, Pos2{x: 10, y: 10});
+        assert_eq!(image.data(), &[0; 9]);
+
+        let mut image = Image::new(1, 1, 0);
+        image.blit(&Image::new(5, 5, 7), Pos2{x: 0, y: 0});
+        assert_eq!(image.data(), &[7]);
+    }
+
+    #[test]
+    fn fill_rect_clamps()
+    {
+        let mut image = Image::new(3, 3, 0);
+        image.fill_rect(Pos2{x: 1, y: 1}, Pos2{x: 5, y: 5}, 1);
+
+        assert_eq!(image.data(), &[0, 0, 0, 0, 1, 1, 0, 1, 1]);
+
+        image.fill_rect(Pos2{x: 4, y: 0}, Pos2{x: 2, y: 2}, 2);
+        assert_eq!(image.data(), &[0, 0, 0, 0, 1, 1, 0, 1, 1]);
+    }
+}
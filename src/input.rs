@@ -0,0 +1,344 @@
+use std::{
+    thread,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+    sync::mpsc::{self, Receiver}
+};
+
+use crate::header;
+
+
+pub const CHUNK_SIZE: usize = 1 << 16;
+
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+// which part of the input gets visualized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection
+{
+    Bytes{offset: u64, length: Option<u64>},
+    // percentages of the file length, start inclusive and end exclusive
+    Percent{start: f64, end: f64}
+}
+
+impl Default for Selection
+{
+    fn default() -> Self
+    {
+        Self::Bytes{offset: 0, length: None}
+    }
+}
+
+impl Selection
+{
+    // percentages cant be turned into offsets before the whole length is known
+    pub fn needs_length(&self) -> bool
+    {
+        matches!(self, Self::Percent{..})
+    }
+
+    // byte offset and length (none meaning until the end) in an input of file_length bytes
+    pub fn resolve(&self, file_length: u64) -> (u64, Option<u64>)
+    {
+        match *self
+        {
+            Self::Bytes{offset, length} => (offset, length),
+            Self::Percent{start, end} =>
+            {
+                let to_offset = |percent: f64| (file_length as f64 * percent / 100.0) as u64;
+
+                let start = to_offset(start);
+
+                (start, Some(to_offset(end).saturating_sub(start)))
+            }
+        }
+    }
+
+    fn slice<'a>(&self, bytes: &'a [u8]) -> &'a [u8]
+    {
+        let (offset, length) = self.resolve(bytes.len() as u64);
+
+        let start = (offset as usize).min(bytes.len());
+        let end = length.map(|length| start.saturating_add(length as usize).min(bytes.len()))
+            .unwrap_or(bytes.len());
+
+        &bytes[start..end]
+    }
+}
+
+// how much of the start of the input gets left out before the selection starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderSkip
+{
+    #[default]
+    None,
+    Bytes(u64),
+    // as long as the header of a known format is, nothing if its not one
+    Auto
+}
+
+// the selection moved past the header, fifos cant be looked at ahead of time so auto doesnt skip anything there
+pub fn skip_header(path: &Path, selection: Selection, skip: HeaderSkip) -> io::Result<Selection>
+{
+    let header = match skip
+    {
+        HeaderSkip::None => return Ok(selection),
+        HeaderSkip::Bytes(amount) => amount,
+        HeaderSkip::Auto =>
+        {
+            if is_fifo(path)
+            {
+                return Ok(selection);
+            }
+
+            let mut start = Vec::new();
+            File::open(path)?.take(header::DETECT_LENGTH as u64).read_to_end(&mut start)?;
+
+            header::detect(&start).map(|(_, length)| length as u64).unwrap_or(0)
+        }
+    };
+
+    match selection
+    {
+        Selection::Bytes{offset, length} => Ok(Selection::Bytes{offset: offset + header, length}),
+        Selection::Percent{..} => Err(io::Error::other("cant skip a header with a percentage selection"))
+    }
+}
+
+#[cfg(unix)]
+pub fn is_fifo(path: &Path) -> bool
+{
+    use std::os::unix::fs::FileTypeExt;
+
+    path.metadata().map(|metadata| metadata.file_type().is_fifo()).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &Path) -> bool
+{
+    false
+}
+
+pub fn read_all(path: &Path) -> io::Result<Vec<u8>>
+{
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+// opens the file and seeks to the start of the selection, returns how many bytes to read after it
+fn open_selection(path: &Path, selection: Selection) -> io::Result<(File, Option<u64>)>
+{
+    let mut file = File::open(path)?;
+
+    let (offset, length) = selection.resolve(file.metadata()?.len());
+    file.seek(SeekFrom::Start(offset))?;
+
+    Ok((file, length))
+}
+
+// just the selected part of a regular file
+pub fn reader(path: &Path, selection: Selection) -> io::Result<impl Read>
+{
+    let (file, length) = open_selection(path, selection)?;
+
+    Ok(file.take(length.unwrap_or(u64::MAX)))
+}
+
+// fifos are read through the stream so a slow writer doesnt matter, ends when the writer closes it
+pub fn read_input(path: &Path, selection: Selection) -> io::Result<Vec<u8>>
+{
+    if !is_fifo(path)
+    {
+        if selection == Selection::default()
+        {
+            return read_all(path);
+        }
+
+        let mut bytes = Vec::new();
+        reader(path, selection)?.read_to_end(&mut bytes)?;
+
+        return Ok(bytes);
+    }
+
+    // the fifo has no length up front so a percentage can only be cut out after reading all of it
+    let streamed_selection = if selection.needs_length()
+    {
+        Selection::default()
+    }
+    else
+    {
+        selection
+    };
+
+    let mut bytes = Vec::new();
+    for chunk in stream(path.to_owned(), false, streamed_selection)
+    {
+        bytes.extend(chunk?);
+    }
+
+    if selection.needs_length()
+    {
+        bytes = selection.slice(&bytes).to_vec();
+    }
+
+    Ok(bytes)
+}
+
+// reads the file on another thread and sends chunks as they arrive, the channel closes at the end
+// opening a fifo blocks until a writer shows up so that happens on the reader thread too
+// with follow it keeps polling for more data after reaching the end (like tail -f)
+// a percentage selection is taken from the length the file has when its opened
+pub fn stream(path: PathBuf, follow: bool, selection: Selection) -> Receiver<io::Result<Vec<u8>>>
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move ||
+    {
+        let opened = if is_fifo(&path)
+        {
+            // cant seek in a fifo, skipping the offset by reading it instead
+            File::open(&path).and_then(|mut file|
+            {
+                let (offset, length) = selection.resolve(0);
+                io::copy(&mut file.by_ref().take(offset), &mut io::sink())?;
+
+                Ok((file, length))
+            })
+        }
+        else
+        {
+            open_selection(&path, selection)
+        };
+
+        let (mut file, mut left) = match opened
+        {
+            Ok(x) => x,
+            Err(err) =>
+            {
+                let _ = sender.send(Err(err));
+                return;
+            }
+        };
+
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop
+        {
+            let wanted = left.map(|left| (left as usize).min(CHUNK_SIZE)).unwrap_or(CHUNK_SIZE);
+            if wanted == 0
+            {
+                return;
+            }
+
+            match file.read(&mut buffer[..wanted])
+            {
+                Ok(0) =>
+                {
+                    if !follow
+                    {
+                        return;
+                    }
+
+                    thread::sleep(FOLLOW_INTERVAL);
+                },
+                Ok(amount) =>
+                {
+                    if let Some(left) = left.as_mut()
+                    {
+                        *left -= amount as u64;
+                    }
+
+                    if sender.send(Ok(buffer[..amount].to_vec())).is_err()
+                    {
+                        return;
+                    }
+                },
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) =>
+                {
+                    let _ = sender.send(Err(err));
+                    return;
+                }
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn stream_reads_everything()
+    {
+        let path = env::temp_dir().join(format!("binvis_stream_test_{}", process::id()));
+
+        let bytes: Vec<u8> = (0..CHUNK_SIZE * 3 + 17).map(|x| (x % 251) as u8).collect();
+        fs::write(&path, &bytes).unwrap();
+
+        let streamed: Vec<u8> = stream(path.clone(), false, Selection::default()).into_iter()
+            .flat_map(|chunk| chunk.unwrap())
+            .collect();
+
+        let selection = Selection::Bytes{offset: 100, length: Some(CHUNK_SIZE as u64 + 5)};
+        let streamed_part: Vec<u8> = stream(path.clone(), false, selection).into_iter()
+            .flat_map(|chunk| chunk.unwrap())
+            .collect();
+
+        let read_part = read_input(&path, selection).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(streamed, bytes);
+        assert_eq!(streamed_part, &bytes[100..CHUNK_SIZE + 105]);
+        assert_eq!(read_part, streamed_part);
+        assert!(!is_fifo(&path));
+    }
+
+    #[test]
+    fn skips_headers()
+    {
+        let path = env::temp_dir().join(format!("binvis_header_test_{}", process::id()));
+
+        let mut bytes = b"BM".to_vec();
+        bytes.resize(300, 7);
+        bytes[10..14].copy_from_slice(&[54, 0, 0, 0]);
+        fs::write(&path, &bytes).unwrap();
+
+        let selection = Selection::Bytes{offset: 10, length: Some(20)};
+
+        let manual = skip_header(&path, selection, HeaderSkip::Bytes(100)).unwrap();
+        let auto = skip_header(&path, Selection::default(), HeaderSkip::Auto).unwrap();
+        let nothing = skip_header(&path, selection, HeaderSkip::None).unwrap();
+
+        fs::write(&path, b"not a known format").unwrap();
+        let unknown = skip_header(&path, selection, HeaderSkip::Auto).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(manual, Selection::Bytes{offset: 110, length: Some(20)});
+        assert_eq!(auto, Selection::Bytes{offset: 54, length: None});
+        assert_eq!(nothing, selection);
+        assert_eq!(unknown, selection);
+    }
+
+    #[test]
+    fn percent_selection()
+    {
+        let bytes: Vec<u8> = (0..200).collect();
+
+        assert_eq!(Selection::Percent{start: 0.0, end: 100.0}.slice(&bytes), &bytes[..]);
+        assert_eq!(Selection::Percent{start: 45.0, end: 55.0}.slice(&bytes), &bytes[90..110]);
+        assert_eq!(Selection::Percent{start: 10.0, end: 20.0}.resolve(1000), (100, Some(100)));
+        assert_eq!(Selection::Bytes{offset: 150, length: Some(100)}.slice(&bytes), &bytes[150..]);
+        assert!(Selection::Bytes{offset: 300, length: None}.slice(&bytes).is_empty());
+    }
+}
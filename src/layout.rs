@@ -0,0 +1,154 @@
+use crate::image::{Image, Pos2};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation
+{
+    R0,
+    R90,
+    R180,
+    R270
+}
+
+impl Orientation
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("0", Self::R0),
+        ("90", Self::R90),
+        ("180", Self::R180),
+        ("270", Self::R270)
+    ];
+
+    pub fn quarter_turns(&self) -> usize
+    {
+        match self
+        {
+            Self::R0 => 0,
+            Self::R90 => 1,
+            Self::R180 => 2,
+            Self::R270 => 3
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout
+{
+    RowMajor,
+    Hilbert
+}
+
+impl Layout
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("rowmajor", Self::RowMajor),
+        ("hilbert", Self::Hilbert)
+    ];
+
+    // hilbert keeps neighboring values next to each other on screen but needs a power of 2 size
+    pub fn arrange<T: Clone>(&self, values: impl IntoIterator<Item=T>, size: usize, empty: T) -> Image<T>
+    {
+        match self
+        {
+            Self::RowMajor => linear_layout(values, size, empty),
+            Self::Hilbert => hilbert_layout(values, size, empty)
+        }
+    }
+}
+
+pub fn orient<T: Clone>(mut image: Image<T>, orientation: Orientation, mirror: bool) -> Image<T>
+{
+    for _ in 0..orientation.quarter_turns()
+    {
+        image = image.rotate_90();
+    }
+
+    if mirror
+    {
+        image.flip_horizontal();
+    }
+
+    image
+}
+
+// where a position from before orient ends up after it
+pub fn orient_point(
+    mut pos: Pos2<usize>,
+    mut width: usize,
+    mut height: usize,
+    orientation: Orientation,
+    mirror: bool
+) -> Pos2<usize>
+{
+    for _ in 0..orientation.quarter_turns()
+    {
+        pos = Pos2{x: height - 1 - pos.y, y: pos.x};
+
+        (width, height) = (height, width);
+    }
+
+    if mirror
+    {
+        pos.x = width - 1 - pos.x;
+    }
+
+    pos
+}
+
+// how many bytes each cell covers so that all of them fit in the given amount of cells
+pub fn bytes_per_cell(len: usize, cells: usize) -> usize
+{
+    len.div_ceil(cells).max(1)
+}
+
+// places values along the curve, cells past the end of values stay empty
+pub fn hilbert_layout<T: Clone>(values: impl IntoIterator<Item=T>, size: usize, empty: T) -> Image<T>
+{
+    let mut data: Vec<T> = values.into_iter().take(size * size).collect();
+    data.resize(size * size, empty);
+
+    let mut image = Image::from_data(size, size, data);
+    image.unhilbertify();
+
+    image
+}
+
+// row-major with the given width, as many rows as needed to fit all values
+pub fn linear_layout<T: Clone>(values: impl IntoIterator<Item=T>, width: usize, empty: T) -> Image<T>
+{
+    let mut data: Vec<T> = values.into_iter().collect();
+
+    let height = data.len().div_ceil(width).max(1);
+    data.resize(width * height, empty);
+
+    Image::from_data(width, height, data)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn orient_point_follows_orient()
+    {
+        let (width, height) = (4, 3);
+
+        for &(_, orientation) in Orientation::NAMES
+        {
+            for mirror in [false, true]
+            {
+                let image = Image::from_data(width, height, (0..width * height).collect());
+                let oriented = orient(image, orientation, mirror);
+
+                for index in 0..width * height
+                {
+                    let pos = Image::<usize>::index_to_pos_assoc(width, index);
+                    let moved = orient_point(pos, width, height, orientation, mirror);
+
+                    assert_eq!(oriented[moved], index, "{orientation:?}, mirror: {mirror}");
+                }
+            }
+        }
+    }
+}
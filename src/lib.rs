@@ -0,0 +1,44 @@
+use image::{Image, Pos2};
+
+pub mod config;
+pub mod input;
+pub mod header;
+pub mod image;
+pub mod hilbert;
+pub mod layout;
+pub mod color;
+pub mod counts;
+pub mod contour;
+pub mod entropy;
+pub mod stats;
+pub mod spectrogram;
+pub mod selfdiff;
+pub mod disasm;
+pub mod render;
+pub mod text;
+pub mod overlay;
+pub mod export;
+pub mod budget;
+pub mod batch;
+pub mod benchmark;
+pub mod check;
+pub mod window;
+
+
+// counts stop at u32::MAX instead of wrapping, a big enough disk image of zeros gets there
+pub fn put_points(image: &mut Image<u32>, bytes: &[u8])
+{
+    for (&x, &y) in bytes.iter().zip(bytes.iter().skip(1))
+    {
+        let count = &mut image[Pos2{x: x as usize, y: y as usize}];
+        *count = count.saturating_add(1);
+    }
+}
+
+pub fn digraph_counts(bytes: &[u8]) -> Image<u32>
+{
+    let mut image = Image::new(256, 256, 0);
+    put_points(&mut image, bytes);
+
+    image
+}
@@ -1,421 +1,193 @@
 use std::{
-    fs::File,
-    io::Read,
     env,
-    thread,
-    time::Duration,
-    ops::{Index, IndexMut}
+    process,
+    path::Path
 };
 
-use sdl2::{
-    Sdl,
-    EventPump,
-    pixels::{PixelFormatEnum, Color},
-    event::Event,
-    video::{WindowContext, Window},
-    render::{Canvas, TextureCreator, Texture}
+use binvis::{
+    input,
+    export,
+    batch,
+    benchmark,
+    check,
+    budget::{self, Loaded},
+    config::{Config, Command},
+    render::render,
+    window::{WindowHolder, DrawerWindow}
 };
 
 
-struct WindowHolder
-{
-    ctx: Sdl,
-    canvas: Canvas<Window>
-}
-
-impl WindowHolder
+fn main()
 {
-    pub fn new(width: u32, height: u32) -> Self
-    {
-        let ctx = sdl2::init().unwrap();
-
-        let video = ctx.video().unwrap();
-
-        let window = video.window("binary visualizer!", width, height)
-            .build()
-            .unwrap();
-
-        let canvas = window.into_canvas()
-            .build()
-            .unwrap();
-
-        Self{ctx, canvas}
-    }
-
-    pub fn events(&self) -> EventPump
-    {
-        self.ctx.event_pump().unwrap()
-    }
-
-    pub fn texture_creator(&self) -> TextureCreator<WindowContext>
-    {
-        self.canvas.texture_creator()
-    }
-
-    pub fn draw(&mut self, texture: &Texture)
+    let config = Config::parse(env::args()).unwrap_or_else(|err|
     {
-        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
-        self.canvas.clear();
-
-        self.canvas.copy(texture, None, None).unwrap();
+        eprintln!("{err}");
 
-        self.canvas.present();
-    }
-}
-
-struct DrawerWindow<'a>
-{
-    events: EventPump,
-    window: WindowHolder,
-    texture: Texture<'a>
-}
+        process::exit(1)
+    });
 
-impl<'a> DrawerWindow<'a>
-{
-    pub fn new(
-        window: WindowHolder,
-        texture_creator: &'a TextureCreator<WindowContext>,
-        image: &Image
-    ) -> Self
+    if config.command == Command::Benchmark
     {
-        let texture = texture_creator
-            .create_texture_static(
-                PixelFormatEnum::RGBA32,
-                image.width() as u32,
-                image.height() as u32
-            ).unwrap();
-
-        let mut this = Self{events: window.events(), window, texture};
-
-        this.update(image);
+        match benchmark::benchmark(&config)
+        {
+            Ok(benchmark) => println!("{benchmark}"),
+            Err(err) =>
+            {
+                eprintln!("cant read {}: {err}", config.input);
 
-        this
-    }
+                process::exit(1)
+            }
+        }
 
-    pub fn update(&mut self, image: &Image)
-    {
-        let data = image.data_raw();
-        self.texture.update(None, &data, image.width() * 4).unwrap();
+        return;
     }
 
-    pub fn wait_exit(mut self)
+    if let (Command::Check, Some(baseline)) = (config.command, &config.check_baseline)
     {
-        loop
+        match check::check(&config, baseline)
         {
-            for event in self.events.poll_iter()
+            Ok(check) =>
             {
-                match event
-                {
-                    Event::Quit{..} => return,
-                    _ => ()
-                }
-            }
+                println!("{check}");
 
-            self.window.draw(&self.texture);
+                process::exit(if check.passed() { 0 } else { 1 })
+            },
+            Err(err) =>
+            {
+                eprintln!("cant check {} against {}: {err}", config.input, baseline.display());
 
-            thread::sleep(Duration::from_millis(1000 / 60));
+                process::exit(2)
+            }
         }
     }
-}
 
-struct Image<T=Color>
-{
-    data: Vec<T>,
-    width: usize,
-    height: usize
-}
-
-impl<T> Image<T>
-where
-    T: Clone
-{
-    pub fn new(width: usize, height: usize, c: T) -> Self
+    if config.compute_only
     {
-        Self{
-            data: vec![c; width * height],
-            width,
-            height
-        }
-    }
+        match benchmark::compute_only(&config)
+        {
+            Ok(timings) => println!("{timings}"),
+            Err(err) =>
+            {
+                eprintln!("cant read {}: {err}", config.input);
 
-    pub fn map<F, U>(self, f: F) -> Image<U>
-    where
-        F: FnMut(T) -> U
-    {
-        Image{
-            data: self.data.into_iter().map(f).collect(),
-            width: self.width,
-            height: self.height
+                process::exit(1)
+            }
         }
+
+        return;
     }
 
-    #[allow(dead_code)]
-    pub fn unhilbertify(&mut self)
+    if let Some(directory) = &config.output_dir
     {
-        assert_eq!(self.width, self.height);
-
-        let size = self.width;
-        let curve = HilbertCurve::new(size);
+        let (entries, errors) = batch::export_batch(&config, directory);
 
-        self.remap_positions(|index|
+        for (input, err) in &errors
         {
-            let pos = curve.value_to_point(index);
-
-            Self::to_index_assoc(size, pos)
-        });
-    }
+            eprintln!("cant export {input}: {err}");
+        }
 
-    #[allow(dead_code)]
-    pub fn hilbertify(&mut self)
-    {
-        assert_eq!(self.width, self.height);
+        if let Some(index) = &config.html
+        {
+            if let Err(err) = batch::write_index(index, &entries)
+            {
+                eprintln!("cant write {}: {err}", index.display());
 
-        let size = self.width;
-        let curve = HilbertCurve::new(size);
+                process::exit(1)
+            }
+        }
 
-        self.remap_positions(|index|
+        if !errors.is_empty()
         {
-            let pos = Self::index_to_pos_assoc(size, index);
+            process::exit(1)
+        }
 
-            curve.point_to_value(pos)
-        });
+        return;
     }
 
-    fn remap_positions(&mut self, mut f: impl FnMut(usize) -> usize)
+    if let Some(output) = &config.output
     {
-        let mut output = self.data.clone();
-
-        self.data.iter().enumerate().for_each(|(i, value)|
+        if let Err(err) = export::export(&config, output)
         {
-            let new_position = f(i);
+            eprintln!("cant export {} to {}: {err}", config.input, output.display());
 
-            output[new_position] = value.clone();
-        });
+            process::exit(1)
+        }
 
-        self.data = output;
+        return;
     }
 
-    pub fn width(&self) -> usize
-    {
-        self.width
-    }
+    let input_path = Path::new(&config.input);
 
-    pub fn height(&self) -> usize
-    {
-        self.height
-    }
+    // a fifo might never reach the end so it cant be read all at once
+    // (unless a percentage of it is wanted, then theres no choice but to wait for the end)
+    let streamed = config.follow || (input::is_fifo(input_path) && !config.selection.needs_length());
 
-    pub fn to_index(&self, pos: Pos2<usize>) -> usize
+    if let Err(err) = budget::check(input_path, &config)
     {
-        Self::to_index_assoc(self.width, pos)
-    }
+        eprintln!("{err}");
 
-    pub fn to_index_assoc(width: usize, pos: Pos2<usize>) -> usize
-    {
-        pos.y * width + pos.x
+        process::exit(1)
     }
 
-    pub fn index_to_pos_assoc(width: usize, index: usize) -> Pos2<usize>
+    let config = budget::located(input_path, &config).unwrap_or_else(|err|
     {
-        Pos2{
-            x: index % width,
-            y: index / width
-        }
-    }
-}
-
-impl Image<Color>
-{
-    pub fn data_raw(&self) -> Vec<u8>
-    {
-        self.data.iter().flat_map(|c|
-        {
-            [c.r, c.g, c.b, c.a]
-        }).collect()
-    }
-}
-
-impl<T> Index<Pos2<usize>> for Image<T>
-where
-    T: Clone
-{
-    type Output = T;
-
-    fn index(&self, index: Pos2<usize>) -> &Self::Output
-    {
-        &self.data[self.to_index(index)]
-    }
-}
+        panic!("provide a valid file, cant open: {} ({err})", config.input)
+    });
 
-impl<T> IndexMut<Pos2<usize>> for Image<T>
-where
-    T: Clone
-{
-    fn index_mut(&mut self, index: Pos2<usize>) -> &mut Self::Output
+    let input = if streamed
     {
-        let index = self.to_index(index);
-
-        &mut self.data[index]
+        Loaded::Bytes(Vec::new())
     }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Pos2<T>
-{
-    x: T,
-    y: T
-}
-
-struct HilbertCurve
-{
-    order: usize
-}
-
-impl HilbertCurve
-{
-    pub fn new(size: usize) -> Self
+    else
     {
-        let mut order = 0;
-
-        let mut current = size;
-        while current > 0
+        let input = budget::load(input_path, &config).unwrap_or_else(|err|
         {
-            current /= 2;
-
-            order += 1;
-        }
-
-        order -= 1;
+            panic!("provide a valid file, cant open: {} ({err})", config.input)
+        });
 
-        if current != 0
+        export::dump_counts(&config, || input.digraph_counts()).unwrap_or_else(|err|
         {
-            panic!("size must be a power of 2");
-        }
+            panic!("cant save counts: {err}")
+        });
 
-        Self{order}
-    }
+        input
+    };
 
-    fn rotate(&self, mut pos: Pos2<usize>, check: Pos2<usize>, value: usize) -> Pos2<usize>
+    let image = if streamed || config.animate
     {
-        if check.y != 0
-        {
-            return pos;
-        }
-
-        if check.x == 1
-        {
-            pos.x = value - 1 - pos.x;
-            pos.y = value - 1 - pos.y;
-        }
-
-        Pos2{x: pos.y, y: pos.x}
+        render(&[], &config)
     }
-
-    #[allow(dead_code)]
-    pub fn point_to_value(&self, mut pos: Pos2<usize>) -> usize
+    else
     {
-        let n = 2_usize.pow(self.order as u32);
+        input.render(&config)
+    };
 
-        (0..self.order).rev().map(|s|
-        {
-            let s = 2_usize.pow(s as u32);
+    let scale = config.scale;
 
-            let rx = ((pos.x & s) > 0) as usize;
-            let ry = ((pos.y & s) > 0) as usize;
+    let holder = WindowHolder::new(image.width() as u32 * scale, image.height() as u32 * scale, &config);
 
-            pos = self.rotate(pos, Pos2{x: rx, y: ry}, n);
+    let texture_creator = holder.texture_creator();
 
-            s * s * ((3 * rx) ^ ry)
-        }).sum()
-    }
+    let mut window = DrawerWindow::new(holder, &texture_creator, image, &config);
 
-    pub fn value_to_point(&self, mut value: usize) -> Pos2<usize>
+    if streamed
     {
-        let mut pos = Pos2{x: 0, y: 0};
-
-        for s in 0..self.order
+        let selection = input::skip_header(input_path, config.selection, config.header).unwrap_or_else(|err|
         {
-            let s = 2_usize.pow(s as u32);
-
-            let rx = (value / 2) & 1;
-            let ry = (value ^ rx) & 1;
-
-            pos = self.rotate(pos, Pos2{x: rx, y: ry}, s);
-
-            pos.x += s * rx;
-            pos.y += s * ry;
-
-            value /= 4;
-        }
+            panic!("provide a valid file, cant open: {} ({err})", config.input)
+        });
 
-        pos
+        window.run_stream(input::stream(input_path.to_owned(), config.follow, selection), &config);
     }
-}
-
-fn put_points(image: &mut Image<u32>, bytes: Vec<u8>)
-{
-    for (&x, &y) in bytes.iter().zip(bytes.iter().skip(1))
+    else if config.animate
     {
-        image[Pos2{x: x as usize, y: y as usize}] += 1;
+        // --animate cant go over --max-memory so all of the bytes are there
+        window.run_animation(input.bytes(), &config);
     }
-}
-
-fn main()
-{
-    let input_path = env::args().nth(1).expect("provide input file plz");
-    let mut input_file = File::open(&input_path).unwrap_or_else(|err|
-    {
-        panic!("provide a valid file, cant open: {} ({err})", input_path)
-    });
-
-    let mut input_bytes = Vec::new();
-    input_file.read_to_end(&mut input_bytes).unwrap();
-
-    let image_size = 256;
-
-    let mut image: Image<u32> = Image::new(image_size, image_size, 0);
-    let top_value = input_bytes.len() / (image_size * image_size);
-
-    put_points(&mut image, input_bytes);
-
-    let image = image.map(|v|
+    else
     {
-        let v = v as f64 / top_value as f64;
-
-        let c = (v * 256.0).clamp(0.0, 255.0) as u8;
-
-        Color::RGB(c, c, c)
-    });
-
-    let scale = 2;
+        window.set_histogram(input.histogram());
 
-    let holder = WindowHolder::new(image_size as u32 * scale, image_size as u32 * scale);
-
-    let texture_creator = holder.texture_creator();
-
-    let window = DrawerWindow::new(holder, &texture_creator, &image);
-
-    window.wait_exit();
-}
-
-#[cfg(test)]
-mod tests
-{
-    use super::*;
-
-    #[test]
-    fn inverse_hilbert()
-    {
-        let n = 512;
-
-        let curve = HilbertCurve::new(n);
-
-        let total = n * n;
-        for i in 0..total
-        {
-            let point = curve.value_to_point(i);
-
-            assert_eq!(curve.point_to_value(point), i);
-        }
+        window.wait_exit(&input);
     }
 }
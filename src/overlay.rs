@@ -0,0 +1,450 @@
+use std::time::Duration;
+
+use sdl2::pixels::Color;
+
+use crate::{
+    image::{Image, Pos2},
+    hilbert::HilbertCurve,
+    config::Config,
+    layout::{self, bytes_per_cell, Orientation},
+    stats::ByteClass,
+    color::{class_color, blend},
+    text::{draw_text, text_width, GLYPH_HEIGHT}
+};
+
+
+const CURVE_LEVELS: usize = 3;
+
+fn curve_levels(size: usize) -> usize
+{
+    CURVE_LEVELS.min(size.trailing_zeros() as usize)
+}
+
+// boundaries of the first few recursion levels, coarser levels are brighter
+pub fn curve_grid(image: &mut Image<Color>)
+{
+    let size = image.width();
+
+    for level in (1..=curve_levels(size)).rev()
+    {
+        let block = size >> level;
+
+        let brightness = (255 / level) as u8;
+        let color = Color::RGB(brightness, brightness / 4, brightness / 4);
+
+        for i in 1..(size / block)
+        {
+            image.fill_rect(Pos2{x: i * block, y: 0}, Pos2{x: 1, y: size}, color);
+            image.fill_rect(Pos2{x: 0, y: i * block}, Pos2{x: size, y: 1}, color);
+        }
+    }
+}
+
+// marks where the curve enters each block of the deepest drawn level with its curve value
+pub fn curve_labels(image: &mut Image<Color>, size: usize, orientation: Orientation, mirror: bool)
+{
+    let curve = HilbertCurve::new(size);
+
+    let block = size >> curve_levels(size);
+    let block_cells = block * block;
+
+    for value in (0..size * size).step_by(block_cells)
+    {
+        let pos = layout::orient_point(curve.value_to_point(value), size, size, orientation, mirror);
+
+        image[pos] = Color::RGB(255, 255, 0);
+
+        let text = value.to_string();
+        draw_text(image, label_position(image, pos, &text), &text, Color::RGB(255, 255, 0));
+    }
+}
+
+// just below and to the right of the point, flipped to the other side where that would go off the image
+fn label_position(image: &Image<Color>, pos: Pos2<usize>, text: &str) -> Pos2<usize>
+{
+    let offset = 2;
+
+    let width = text_width(text);
+
+    let x = if pos.x + offset + width > image.width()
+    {
+        pos.x.saturating_sub(offset + width)
+    }
+    else
+    {
+        pos.x + offset
+    };
+
+    let y = if pos.y + offset + GLYPH_HEIGHT > image.height()
+    {
+        pos.y.saturating_sub(offset + GLYPH_HEIGHT)
+    }
+    else
+    {
+        pos.y + offset
+    };
+
+    Pos2{x, y}
+}
+
+// file offsets at evenly spaced points along the curve including where it starts and ends
+pub fn ruler(image: &mut Image<Color>, len: usize, config: &Config)
+{
+    if len == 0
+    {
+        return;
+    }
+
+    let size = config.size;
+    let curve = HilbertCurve::new(size);
+
+    let chunk = bytes_per_cell(len, size * size);
+
+    let color = Color::RGB(255, 80, 255);
+
+    let marks = config.ruler_marks;
+    for mark in 0..marks
+    {
+        let offset = if mark + 1 == marks
+        {
+            len
+        }
+        else
+        {
+            len * mark / (marks - 1)
+        };
+
+        // the end offset is one past the last byte so it goes on the last cell instead
+        let value = offset.min(len - 1) / chunk;
+
+        let point = curve.value_to_point(value);
+        let pos = layout::orient_point(point, size, size, config.orientation, config.mirror);
+
+        image.fill_rect(Pos2{x: pos.x.saturating_sub(1), y: pos.y.saturating_sub(1)}, Pos2{x: 3, y: 3}, color);
+
+        // labelled with where the byte is in the file, not just in the shown bytes
+        let text = format!("{:#X}", offset as u64 + config.file_offset);
+        draw_text(image, label_position(image, pos, &text), &text, color);
+    }
+}
+
+pub const HIGHLIGHT_COLOR: Color = Color::RGB(255, 220, 0);
+
+const HIGHLIGHT_ALPHA: f64 = 0.6;
+
+// a range of byte offsets (end not included) into the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight
+{
+    pub start: usize,
+    pub end: usize,
+    pub color: Color,
+    pub label: Option<String>
+}
+
+// tints the cells along the curve that the ranges cover, labels go where each range starts
+// ranges are file offsets so the ones outside the visualized bytes dont show up at all
+pub fn highlights(image: &mut Image<Color>, highlights: &[Highlight], len: usize, config: &Config)
+{
+    let size = config.size;
+    let curve = HilbertCurve::new(size);
+
+    let chunk = bytes_per_cell(len, size * size);
+
+    let position = |value: usize|
+    {
+        layout::orient_point(curve.value_to_point(value), size, size, config.orientation, config.mirror)
+    };
+
+    let shown_start = usize::try_from(config.file_offset).unwrap_or(usize::MAX);
+
+    // cells each range covers
+    let shown: Vec<_> = highlights.iter().filter_map(|highlight|
+    {
+        let start = highlight.start.saturating_sub(shown_start);
+        let end = highlight.end.saturating_sub(shown_start).min(len);
+
+        (start < end).then(|| (start / chunk, end.div_ceil(chunk), highlight))
+    }).collect();
+
+    for &(first, last, highlight) in &shown
+    {
+        for value in first..last
+        {
+            let pos = position(value);
+
+            image[pos] = blend(image[pos], highlight.color, HIGHLIGHT_ALPHA);
+        }
+    }
+
+    // after all the tinting so other ranges dont cover up the text
+    for &(first, _, highlight) in &shown
+    {
+        if let Some(label) = &highlight.label
+        {
+            let pos = position(first);
+
+            draw_text(image, label_position(image, pos, label), label, highlight.color);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner
+{
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight
+}
+
+impl Corner
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("top-left", Self::TopLeft),
+        ("top-right", Self::TopRight),
+        ("bottom-left", Self::BottomLeft),
+        ("bottom-right", Self::BottomRight)
+    ];
+
+    // top left position of a box this big in this corner, margin pixels away from the edges
+    pub fn place(&self, image_size: Pos2<usize>, size: Pos2<usize>, margin: usize) -> Pos2<usize>
+    {
+        let far = |image: usize, size: usize| image.saturating_sub(size + margin);
+
+        match self
+        {
+            Self::TopLeft => Pos2{x: margin, y: margin},
+            Self::TopRight => Pos2{x: far(image_size.x, size.x), y: margin},
+            Self::BottomLeft => Pos2{x: margin, y: far(image_size.y, size.y)},
+            Self::BottomRight => Pos2{x: far(image_size.x, size.x), y: far(image_size.y, size.y)}
+        }
+    }
+}
+
+const LEGEND_PADDING: usize = 2;
+const LEGEND_SWATCH: usize = GLYPH_HEIGHT;
+
+// which color each byte class is, in a small box so it covers as little of the data as it can
+pub fn class_legend(image: &mut Image<Color>, corner: Corner)
+{
+    let row_height = LEGEND_SWATCH + 1;
+
+    let text_width = ByteClass::ALL.iter().map(|class| text_width(class.name())).max().unwrap_or(0);
+    let size = Pos2{
+        x: LEGEND_PADDING * 2 + LEGEND_SWATCH + 2 + text_width,
+        y: LEGEND_PADDING * 2 + ByteClass::ALL.len() * row_height - 1
+    };
+
+    let start = corner.place(Pos2{x: image.width(), y: image.height()}, size, 1);
+
+    image.fill_rect(start, size, Color::RGB(48, 48, 48));
+
+    for (row, class) in ByteClass::ALL.into_iter().enumerate()
+    {
+        let y = start.y + LEGEND_PADDING + row * row_height;
+        let x = start.x + LEGEND_PADDING;
+
+        image.fill_rect(Pos2{x, y}, Pos2{x: LEGEND_SWATCH, y: LEGEND_SWATCH}, class_color(class));
+
+        let text_pos = Pos2{x: x + LEGEND_SWATCH + 2, y};
+        draw_text(image, text_pos, class.name(), Color::RGB(255, 255, 255));
+    }
+}
+
+// byte value counts of whats been shown so far, only the new bytes get counted on every update
+pub struct LiveHistogram
+{
+    counts: [u64; 256],
+    start: usize,
+    end: usize,
+    corner: Corner,
+    size: Pos2<usize>
+}
+
+impl LiveHistogram
+{
+    pub fn new(corner: Corner, size: Pos2<usize>) -> Self
+    {
+        Self{counts: [0; 256], start: 0, end: 0, corner, size}
+    }
+
+    // bytes[start..end] is whats currently shown, if that doesnt just extend whats been counted it starts over
+    pub fn update(&mut self, bytes: &[u8], start: usize, end: usize)
+    {
+        if start != self.start || end < self.end
+        {
+            self.counts = [0; 256];
+            self.start = start;
+            self.end = start;
+        }
+
+        bytes[self.end..end].iter().for_each(|&x| self.counts[x as usize] += 1);
+        self.end = end;
+    }
+
+    // counts from somewhere other than bytes, the next update starts over
+    pub fn set(&mut self, counts: [u64; 256])
+    {
+        self.counts = counts;
+        self.start = 0;
+        self.end = usize::MAX;
+    }
+
+    // one bar per column with the byte values spread over the width, scaled to the most common value
+    pub fn draw(&self, image: &mut Image<Color>)
+    {
+        let size = Pos2{x: self.size.x.min(image.width()), y: self.size.y.min(image.height())};
+        if size.x == 0 || size.y == 0
+        {
+            return;
+        }
+
+        let start = self.corner.place(Pos2{x: image.width(), y: image.height()}, size, 1);
+
+        image.fill_rect(start, size, Color::RGB(24, 24, 24));
+
+        let highest = self.counts.iter().copied().max().unwrap_or(0).max(1);
+
+        for x in 0..size.x
+        {
+            let values = (x * 256 / size.x)..((x + 1) * 256 / size.x).max(x * 256 / size.x + 1);
+            let count = self.counts[values].iter().copied().max().unwrap_or(0);
+
+            let height = ((count as f64 / highest as f64) * size.y as f64).round() as usize;
+
+            let bar_start = Pos2{x: start.x + x, y: start.y + size.y - height};
+            image.fill_rect(bar_start, Pos2{x: 1, y: height}, Color::RGB(255, 200, 60));
+        }
+    }
+}
+
+// seconds for the trail to fade to half
+const TRAIL_HALF_LIFE: f64 = 20.0;
+
+const TRAIL_RADIUS: usize = 1;
+
+// remembers where the mouse has been so its easier to tell what was already looked at
+pub struct HeatTrail
+{
+    heat: Image<f32>
+}
+
+impl HeatTrail
+{
+    pub fn new(width: usize, height: usize) -> Self
+    {
+        Self{heat: Image::new(width, height, 0.0)}
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize)
+    {
+        *self = Self::new(width, height);
+    }
+
+    pub fn touch(&mut self, pos: Pos2<usize>)
+    {
+        let start = Pos2{x: pos.x.saturating_sub(TRAIL_RADIUS), y: pos.y.saturating_sub(TRAIL_RADIUS)};
+        let size = TRAIL_RADIUS * 2 + 1;
+
+        self.heat.fill_rect(start, Pos2{x: size, y: size}, 1.0);
+    }
+
+    pub fn decay(&mut self, elapsed: Duration)
+    {
+        let factor = 0.5_f64.powf(elapsed.as_secs_f64() / TRAIL_HALF_LIFE) as f32;
+
+        self.heat.data_mut().iter_mut().for_each(|x| *x *= factor);
+    }
+
+    pub fn overlay(&self) -> Image<Color>
+    {
+        self.heat.clone().map(|heat|
+        {
+            Color::RGBA(0, 255, 255, (heat * 150.0) as u8)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::stats::histogram;
+
+    use super::*;
+
+    #[test]
+    fn highlights_cover_their_cells()
+    {
+        let config = Config{size: 4, ..Default::default()};
+
+        let mut image = Image::new(4, 4, Color::RGB(0, 0, 0));
+
+        let highlight = Highlight{start: 8, end: 20, color: Color::RGB(0, 255, 0), label: None};
+        highlights(&mut image, &[highlight], 32, &config);
+
+        // 2 bytes per cell so thats cells 4 to 9 along the curve
+        let curve = HilbertCurve::new(4);
+        for value in 0..16
+        {
+            let tinted = image[curve.value_to_point(value)].g != 0;
+
+            assert_eq!(tinted, (4..10).contains(&value), "cell {value}");
+        }
+    }
+
+    #[test]
+    fn highlights_use_file_offsets()
+    {
+        // the shown bytes start 100 bytes into the file
+        let config = Config{size: 4, file_offset: 100, ..Default::default()};
+
+        let mut image = Image::new(4, 4, Color::RGB(0, 0, 0));
+
+        let color = Color::RGB(0, 255, 0);
+        let ranges = [
+            Highlight{start: 90, end: 104, color, label: None},
+            Highlight{start: 0, end: 100, color, label: Some("before".to_owned())},
+            Highlight{start: 132, end: 200, color, label: Some("after".to_owned())}
+        ];
+        highlights(&mut image, &ranges, 32, &config);
+
+        // only the first range reaches into the shown bytes, up to byte 4 of them
+        let curve = HilbertCurve::new(4);
+        for value in 0..16
+        {
+            let tinted = image[curve.value_to_point(value)].g != 0;
+
+            assert_eq!(tinted, (0..2).contains(&value), "cell {value}");
+        }
+    }
+
+    #[test]
+    fn live_histogram_follows_shown_bytes()
+    {
+        let bytes: Vec<u8> = (0..1000_u32).map(|x| (x * x % 251) as u8).collect();
+
+        let mut live = LiveHistogram::new(Corner::TopLeft, Pos2{x: 64, y: 16});
+
+        live.update(&bytes, 0, 300);
+        live.update(&bytes, 0, 800);
+        assert_eq!(live.counts, histogram(&bytes[..800]));
+
+        // a sliding window and going backwards both start over
+        live.update(&bytes, 500, 900);
+        assert_eq!(live.counts, histogram(&bytes[500..900]));
+
+        live.update(&bytes, 500, 600);
+        assert_eq!(live.counts, histogram(&bytes[500..600]));
+
+        // counts that were set some other way get replaced by the next update
+        live.set(histogram(&bytes));
+        assert_eq!(live.counts, histogram(&bytes));
+
+        live.update(&bytes, 500, 600);
+        assert_eq!(live.counts, histogram(&bytes[500..600]));
+
+        let mut image = Image::new(32, 8, Color::RGB(0, 0, 0));
+        live.draw(&mut image);
+    }
+}
@@ -0,0 +1,794 @@
+use std::iter;
+
+use sdl2::pixels::Color;
+
+use crate::{
+    digraph_counts,
+    config::Config,
+    image::{Image, Pos2},
+    color::{hsv_to_rgb, class_color, accumulated_alpha, ColorPass, Colormap},
+    counts::{log_ratios, pmi},
+    contour,
+    entropy::{entropy, sliding_entropy},
+    stats::{average, histogram, string_fractions, printable_fraction, nonzero_fraction, repetition_fraction, ByteClass},
+    text::{draw_text, text_width, GLYPH_HEIGHT},
+    spectrogram::spectrogram,
+    selfdiff::{auto_lag, match_fractions, hamming_fractions},
+    disasm::{boundaries, chunk_summary},
+    layout::{self, bytes_per_cell, Layout},
+    budget::Accumulator,
+    overlay
+};
+
+
+// smaller cells than this get their channels measured over this many bytes instead
+const CHANNEL_WINDOW: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coloring
+{
+    Value,
+    Hsv,
+    Class
+}
+
+impl Coloring
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("value", Self::Value),
+        ("hsv", Self::Hsv),
+        ("class", Self::Class)
+    ];
+}
+
+// what a channel of the hsv coloring shows, all of them go from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelSource
+{
+    Value,
+    Entropy,
+    Repetition,
+    Density
+}
+
+impl ChannelSource
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("value", Self::Value),
+        ("entropy", Self::Entropy),
+        ("repetition", Self::Repetition),
+        ("density", Self::Density)
+    ];
+
+    pub fn measure(&self, bytes: &[u8]) -> f64
+    {
+        match self
+        {
+            Self::Value => average(bytes) / 255.0,
+            Self::Entropy => entropy(bytes) / 8.0,
+            Self::Repetition => repetition_fraction(bytes),
+            Self::Density => nonzero_fraction(bytes)
+        }
+    }
+}
+
+// what the bar next to each row of the records mode shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordStat
+{
+    Entropy,
+    Checksum,
+    Class
+}
+
+impl RecordStat
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("entropy", Self::Entropy),
+        ("checksum", Self::Checksum),
+        ("class", Self::Class)
+    ];
+
+    // records is every record going into a row, usually just one
+    fn color(&self, records: &[&[u8]]) -> Color
+    {
+        match self
+        {
+            Self::Entropy =>
+            {
+                let total = records.iter().map(|record| entropy(record)).sum::<f64>();
+
+                Colormap::Magma.color_fraction(total / records.len() as f64 / 8.0)
+            },
+            Self::Checksum =>
+            {
+                // records that are the same get the same hue, the slightest difference usually changes it
+                let sum = records.iter().flat_map(|record| record.iter()).fold(0_u8, |sum, &x|
+                {
+                    sum.rotate_left(3) ^ x
+                });
+
+                hsv_to_rgb(sum as f64 / 256.0, 0.8, 1.0)
+            },
+            Self::Class =>
+            {
+                let firsts: Vec<u8> = records.iter().filter_map(|record| record.first().copied()).collect();
+
+                class_color(ByteClass::majority(&firsts))
+            }
+        }
+    }
+}
+
+// where the split mode cuts the bytes in two
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitPoint
+{
+    Percent(f64),
+    Offset(usize)
+}
+
+impl Default for SplitPoint
+{
+    fn default() -> Self
+    {
+        Self::Percent(50.0)
+    }
+}
+
+impl SplitPoint
+{
+    pub fn offset(&self, len: usize) -> usize
+    {
+        match *self
+        {
+            Self::Percent(percent) => (len as f64 * percent / 100.0) as usize,
+            Self::Offset(offset) => offset.min(len)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualizationMode
+{
+    Digraph,
+    Hilbert,
+    Entropy,
+    Strip,
+    Spectrogram,
+    SelfDiff,
+    Presence,
+    Plot,
+    Pmi,
+    Opcodes,
+    Records,
+    Integral,
+    Hamming,
+    Split,
+    Strings
+}
+
+impl VisualizationMode
+{
+    pub const NAMES: &'static [(&'static str, Self)] = &[
+        ("digraph", Self::Digraph),
+        ("hilbert", Self::Hilbert),
+        ("entropy", Self::Entropy),
+        ("strip", Self::Strip),
+        ("spectrogram", Self::Spectrogram),
+        ("selfdiff", Self::SelfDiff),
+        ("presence", Self::Presence),
+        ("plot", Self::Plot),
+        ("pmi", Self::Pmi),
+        ("opcodes", Self::Opcodes),
+        ("records", Self::Records),
+        ("integral", Self::Integral),
+        ("hamming", Self::Hamming),
+        ("split", Self::Split),
+        ("strings", Self::Strings)
+    ];
+}
+
+pub fn render(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    match config.mode
+    {
+        VisualizationMode::Digraph => render_digraph(&digraph_counts(bytes), bytes.len(), config),
+        VisualizationMode::Hilbert =>
+        {
+            let mut image = render_hilbert(bytes, config);
+
+            if config.debug_curve
+            {
+                overlay::curve_grid(&mut image);
+            }
+
+            let mut image = layout::orient(image, config.orientation, config.mirror);
+
+            if config.debug_curve
+            {
+                overlay::curve_labels(&mut image, config.size, config.orientation, config.mirror);
+            }
+
+            if !config.highlights.is_empty()
+            {
+                overlay::highlights(&mut image, &config.highlights, bytes.len(), config);
+            }
+
+            if config.ruler
+            {
+                overlay::ruler(&mut image, bytes.len(), config);
+            }
+
+            if config.class_legend
+            {
+                overlay::class_legend(&mut image, config.legend_corner);
+            }
+
+            image
+        },
+        VisualizationMode::Entropy => render_entropy(bytes, config),
+        VisualizationMode::Strip => render_strip(bytes, config.size),
+        VisualizationMode::Spectrogram =>
+        {
+            spectrogram(bytes, config.fft_size, config.size).map(|x| config.colormap.color_fraction(x))
+        },
+        VisualizationMode::SelfDiff => render_self_diff(bytes, config),
+        VisualizationMode::Presence => render_presence(&histogram(bytes), config),
+        VisualizationMode::Plot => render_plot(bytes, config),
+        VisualizationMode::Pmi => render_pmi(&digraph_counts(bytes)),
+        VisualizationMode::Opcodes => render_opcodes(bytes, config),
+        VisualizationMode::Records => render_records(bytes, config),
+        VisualizationMode::Integral => render_integral(bytes, config),
+        VisualizationMode::Hamming => render_hamming(bytes, config),
+        VisualizationMode::Split => render_split(bytes, config),
+        VisualizationMode::Strings => render_strings(bytes, config)
+    }
+}
+
+// modes that can be streamable go through the accumulator instead of the bytes
+pub fn render_accumulated(accumulator: &Accumulator, config: &Config) -> Image<Color>
+{
+    match config.mode
+    {
+        VisualizationMode::Digraph => render_digraph(accumulator.counts(), accumulator.len(), config),
+        VisualizationMode::Pmi => render_pmi(accumulator.counts()),
+        VisualizationMode::Presence => render_presence(accumulator.histogram(), config),
+        VisualizationMode::Entropy => render_entropies(&accumulator.entropies(), config),
+        _ => unreachable!("accumulators are only made for streamable modes")
+    }
+}
+
+// for callers that already have the counts (like streams that count incrementally)
+pub fn render_digraph(counts: &Image<u32>, len: usize, config: &Config) -> Image<Color>
+{
+    if let Some(baseline) = &config.baseline
+    {
+        return render_baseline_ratio(counts, baseline);
+    }
+
+    if let Some(amount) = config.contours
+    {
+        return render_contours(counts, amount, config.colormap);
+    }
+
+    // like drawing every pair as a faint point on top of the others, so heavy cells fill in gradually
+    if config.accumulate
+    {
+        return counts.clone().map(|count|
+        {
+            let alpha = accumulated_alpha(count, config.saturation);
+
+            config.colormap.color_fraction(config.scale_mode.apply(alpha))
+        });
+    }
+
+    let top_value = (len / (counts.width() * counts.height())) as f64;
+
+    match config.scale_mode.gamma()
+    {
+        Some(gamma) => ColorPass::new(top_value, gamma, config.colormap).apply(counts),
+        None => counts.clone().map(|count|
+        {
+            config.colormap.color_fraction(config.scale_mode.apply(count as f64 / top_value))
+        })
+    }
+}
+
+const SPLIT_GAP: usize = 2;
+
+// digraphs of the bytes before and after the split point next to each other, appended data
+// or anything else that changes partway through looks different on each side
+// the pair going across the split counts for the side after it so none of them go missing
+fn split_counts(bytes: &[u8], split: usize) -> (Image<u32>, Image<u32>)
+{
+    (digraph_counts(&bytes[..split]), digraph_counts(&bytes[split.saturating_sub(1)..]))
+}
+
+fn render_split(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let split = config.split_point.unwrap_or_default().offset(bytes.len());
+
+    let (before, after) = split_counts(bytes, split);
+
+    let left = render_digraph(&before, split, config);
+    let right = render_digraph(&after, bytes.len() - split, config);
+
+    let mut image = Image::new(left.width() + SPLIT_GAP + right.width(), left.height(), Color::RGB(80, 80, 80));
+
+    image.blit(&left, Pos2{x: 0, y: 0});
+    image.blit(&right, Pos2{x: left.width() + SPLIT_GAP, y: 0});
+
+    image
+}
+
+// lines of equal density like a topographic map with a dim gray digraph under them to see what theyre around
+fn render_contours(counts: &Image<u32>, amount: usize, colormap: Colormap) -> Image<Color>
+{
+    let field = contour::density_field(counts);
+
+    let highest = field.data().iter().copied().fold(0.0, f64::max);
+    let mut image = field.clone().map(|x|
+    {
+        let c = (x / highest.max(f64::EPSILON) * 64.0) as u8;
+
+        Color::RGB(c, c, c)
+    });
+
+    let levels = contour::levels(&field, amount);
+    for (index, &level) in levels.iter().enumerate()
+    {
+        // starts partway along so the lowest level doesnt end up black
+        let color = colormap.color_fraction(0.3 + 0.7 * (index + 1) as f64 / levels.len() as f64);
+
+        let to_pixel = |pos: Pos2<f64>| Pos2{x: pos.x.round() as usize, y: pos.y.round() as usize};
+
+        for (a, b) in contour::segments(&field, level)
+        {
+            image.line(to_pixel(a), to_pixel(b), color);
+        }
+    }
+
+    image
+}
+
+// red where pairs are more common than in the baseline, blue where theyre rarer
+fn render_baseline_ratio(counts: &Image<u32>, baseline: &Image<u32>) -> Image<Color>
+{
+    log_ratios(counts, baseline).map(diverging)
+}
+
+// red for pairs that follow each other more than chance, blue for less and black for never
+fn render_pmi(counts: &Image<u32>) -> Image<Color>
+{
+    pmi(counts).map(|value| value.map(diverging).unwrap_or(Color::RGB(0, 0, 0)))
+}
+
+// red for positive log2 values and blue for negative ones
+fn diverging(value: f64) -> Color
+{
+    // past 2^8 times more or less likely are all the same color
+    let limit = 8.0;
+
+    let c = (value.abs() / limit * 255.0).clamp(0.0, 255.0) as u8;
+
+    if value > 0.0
+    {
+        Color::RGB(c, 0, 0)
+    }
+    else
+    {
+        Color::RGB(0, 0, c)
+    }
+}
+
+fn render_hilbert(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+    let chunk = bytes_per_cell(bytes.len(), size * size);
+
+    let values = (0..bytes.len()).step_by(chunk).map(|start|
+    {
+        let cell = &bytes[start..(start + chunk).min(bytes.len())];
+
+        match config.coloring
+        {
+            Coloring::Value => config.colormap.color_fraction(average(cell) / 255.0),
+            Coloring::Hsv =>
+            {
+                let window = &bytes[start..(start + chunk.max(CHANNEL_WINDOW)).min(bytes.len())];
+
+                // stops before going all the way around so the lowest and highest values dont match
+                let hue = config.hue_source.measure(window) * 0.8;
+                let brightness = config.brightness_source.measure(window);
+
+                hsv_to_rgb(hue, 1.0, brightness)
+            },
+            Coloring::Class => class_color(ByteClass::majority(cell))
+        }
+    });
+
+    layout::hilbert_layout(values, size, Color::RGB(0, 0, 0))
+}
+
+fn render_entropy(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+
+    let step = bytes_per_cell(bytes.len(), size * size);
+    let window = config.entropy_window.unwrap_or(step);
+
+    render_entropies(&sliding_entropy(bytes, window, step), config)
+}
+
+fn render_entropies(entropies: &[f64], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+
+    let values = entropies.iter().map(|entropy|
+    {
+        config.colormap.color_fraction(entropy / 8.0)
+    }).chain(iter::repeat(Color::RGB(0, 0, 0))).take(size * size);
+
+    let image = config.entropy_layout.arrange(values, size, Color::RGB(0, 0, 0));
+
+    orient_hilbert(image, config.entropy_layout, config)
+}
+
+// hue is the average instruction length and brightness how much decoded, code has steady short
+// instructions while data decodes into a mess of lengths with undecodable bytes mixed in
+fn render_opcodes(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+    let chunk = bytes_per_cell(bytes.len(), size * size);
+
+    let boundaries = boundaries(bytes, config.arch);
+
+    let values = boundaries.chunks(chunk).map(|cell|
+    {
+        let (valid, average_len) = chunk_summary(cell);
+
+        // most real instructions are under 8 bytes
+        let hue = (average_len / 8.0).min(1.0) * 0.8;
+
+        hsv_to_rgb(hue, 1.0, valid)
+    });
+
+    let image = layout::hilbert_layout(values, size, Color::RGB(0, 0, 0));
+
+    layout::orient(image, config.orientation, config.mirror)
+}
+
+fn orient_hilbert(image: Image<Color>, layout: Layout, config: &Config) -> Image<Color>
+{
+    if layout == Layout::Hilbert
+    {
+        layout::orient(image, config.orientation, config.mirror)
+    }
+    else
+    {
+        image
+    }
+}
+
+// bright where bytes repeat lag bytes later, repeated blocks show up as solid regions
+fn render_self_diff(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+
+    let lag = config.lag.unwrap_or_else(|| auto_lag(bytes));
+    let chunk = bytes_per_cell(bytes.len().saturating_sub(lag), size * size);
+
+    let values = match_fractions(bytes, lag, chunk).into_iter().map(|x| config.colormap.color_fraction(x));
+
+    let image = config.selfdiff_layout.arrange(values.take(size * size), size, Color::RGB(0, 0, 0));
+
+    orient_hilbert(image, config.selfdiff_layout, config)
+}
+
+// how many bits change from each byte to the next, noisy or compressed data is bright and smooth data dark
+fn render_hamming(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let size = config.size;
+
+    let chunk = bytes_per_cell(bytes.len().saturating_sub(1), size * size);
+
+    let values = hamming_fractions(bytes, chunk).into_iter().map(|x| config.colormap.color_fraction(x));
+
+    let image = config.hamming_layout.arrange(values.take(size * size), size, Color::RGB(0, 0, 0));
+
+    orient_hilbert(image, config.hamming_layout, config)
+}
+
+// values that show up even once are at least this far along the colormap so they stand out from absent ones
+const PRESENT_MINIMUM: f64 = 0.25;
+
+const ABSENT_COLOR: Color = Color::RGB(110, 0, 0);
+
+// a 16x16 grid of the 256 byte values (0 in the top left going along the rows), absent values in red
+fn render_presence(counts: &[u64; 256], config: &Config) -> Image<Color>
+{
+    let highest = counts.iter().copied().max().unwrap_or(0);
+
+    let cell = (config.size / 16).max(1);
+    let gap = usize::from(cell >= 4);
+
+    let label_fits = text_width("FF") + 2 <= cell && GLYPH_HEIGHT + 2 <= cell;
+
+    let mut image = Image::new(cell * 16, cell * 16, Color::RGB(0, 0, 0));
+
+    for (value, &count) in counts.iter().enumerate()
+    {
+        let color = if count == 0
+        {
+            ABSENT_COLOR
+        }
+        else
+        {
+            let fraction = (count as f64).ln_1p() / (highest as f64).ln_1p();
+
+            config.colormap.color_fraction(PRESENT_MINIMUM + (1.0 - PRESENT_MINIMUM) * fraction)
+        };
+
+        let pos = Pos2{x: (value % 16) * cell, y: (value / 16) * cell};
+        image.fill_rect(pos, Pos2{x: cell - gap, y: cell - gap}, color);
+
+        if label_fits
+        {
+            // rough luminance out of 2550
+            let brightness = color.r as u32 * 3 + color.g as u32 * 6 + color.b as u32;
+            let label_color = if brightness > 1280
+            {
+                Color::RGB(0, 0, 0)
+            }
+            else
+            {
+                Color::RGB(255, 255, 255)
+            };
+
+            draw_text(&mut image, Pos2{x: pos.x + 1, y: pos.y + 1}, &format!("{value:02X}"), label_color);
+        }
+    }
+
+    image
+}
+
+const PLOT_ROW_HEIGHT: usize = 64;
+const PLOT_ROW_GAP: usize = 2;
+
+// byte value over the offset as a line, wrapped into rows of --width samples going down the image
+// when theres more bytes than samples each sample is the average of a chunk
+fn render_plot(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let (width, rows) = plot_shape(config);
+
+    let chunk = bytes_per_cell(bytes.len(), width * rows);
+    let samples: Vec<f64> = bytes.chunks(chunk).map(|chunk| average(chunk) / 255.0).collect();
+
+    plot_rows(&samples, width, rows, config.line_color)
+}
+
+// running sum of how far each byte is from the middle value, going up means more high bytes
+// scaled so the lowest point of the whole file is at the bottom of a row and the highest at the top
+fn render_integral(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let (width, rows) = plot_shape(config);
+
+    let chunk = bytes_per_cell(bytes.len(), width * rows);
+
+    let mut sum = 0.0;
+    let sums: Vec<f64> = bytes.chunks(chunk).map(|chunk|
+    {
+        sum += chunk.iter().map(|&x| x as f64 - 127.5).sum::<f64>();
+
+        sum
+    }).collect();
+
+    let lowest = sums.iter().copied().fold(0.0, f64::min);
+    let highest = sums.iter().copied().fold(0.0, f64::max);
+
+    let range = (highest - lowest).max(f64::EPSILON);
+    let samples: Vec<f64> = sums.iter().map(|sum| (sum - lowest) / range).collect();
+
+    plot_rows(&samples, width, rows, config.line_color)
+}
+
+// samples per row and how many rows fit in --size
+fn plot_shape(config: &Config) -> (usize, usize)
+{
+    let width = config.width.unwrap_or(config.size);
+    let rows = (config.size / (PLOT_ROW_HEIGHT + PLOT_ROW_GAP)).max(1);
+
+    (width, rows)
+}
+
+// samples (from 0 at the bottom to 1 at the top) connected by lines, wrapping into rows going down
+fn plot_rows(samples: &[f64], width: usize, rows: usize, color: Color) -> Image<Color>
+{
+    let row_stride = PLOT_ROW_HEIGHT + PLOT_ROW_GAP;
+    let mut image = Image::new(width, rows * row_stride - PLOT_ROW_GAP, Color::RGB(0, 0, 0));
+
+    for row in 1..rows
+    {
+        let y = row * row_stride - PLOT_ROW_GAP;
+        image.fill_rect(Pos2{x: 0, y}, Pos2{x: width, y: PLOT_ROW_GAP}, Color::RGB(40, 40, 40));
+    }
+
+    let point = |index: usize, value: f64|
+    {
+        let y = ((1.0 - value) * (PLOT_ROW_HEIGHT - 1) as f64).round() as usize;
+
+        Pos2{x: index % width, y: (index / width) * row_stride + y}
+    };
+
+    for (index, &value) in samples.iter().enumerate()
+    {
+        let current = point(index, value);
+
+        // lines dont continue across the wrap into the next row
+        let previous = if index % width == 0
+        {
+            current
+        }
+        else
+        {
+            point(index - 1, samples[index - 1])
+        };
+
+        image.line(previous, current, color);
+    }
+
+    image
+}
+
+const RECORD_STAT_WIDTH: usize = 8;
+const RECORD_GAP: usize = 2;
+
+// one row per --record-size record with the stat bar on the left and the bytes themselves after it
+// past --size records (or bytes in a record) neighbouring ones get averaged together into a row (or column)
+fn render_records(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let record_size = config.record_size.expect("records mode is validated to have a record size");
+
+    // the last record can be shorter, its missing bytes show up as the divider color
+    let records: Vec<&[u8]> = bytes.chunks(record_size).collect();
+
+    let per_row = bytes_per_cell(records.len(), config.size);
+    let per_column = bytes_per_cell(record_size, config.size);
+
+    let rows = records.len().div_ceil(per_row).max(1);
+    let columns = record_size.div_ceil(per_column);
+
+    let bytes_start = RECORD_STAT_WIDTH + RECORD_GAP;
+
+    let divider = Color::RGB(24, 24, 24);
+    let mut image = Image::new(bytes_start + columns, rows, divider);
+
+    for (row, group) in records.chunks(per_row).enumerate()
+    {
+        image.fill_rect(Pos2{x: 0, y: row}, Pos2{x: RECORD_STAT_WIDTH, y: 1}, config.record_stat.color(group));
+
+        for column in 0..columns
+        {
+            let start = column * per_column;
+            let end = start + per_column;
+
+            let values: Vec<u8> = group.iter()
+                .flat_map(|record| record.get(start..end.min(record.len())).unwrap_or(&[]))
+                .copied()
+                .collect();
+
+            if values.is_empty()
+            {
+                continue;
+            }
+
+            image[Pos2{x: bytes_start + column, y: row}] = config.colormap.color_fraction(average(&values) / 255.0);
+        }
+    }
+
+    image
+}
+
+const STRINGS_HEIGHT: usize = 64;
+
+// how much of each column worth of bytes is in strings as a filled curve colored by that same amount
+fn render_strings(bytes: &[u8], config: &Config) -> Image<Color>
+{
+    let width = config.size;
+
+    let chunk = bytes_per_cell(bytes.len(), width);
+
+    let mut image = Image::new(width, STRINGS_HEIGHT, Color::RGB(0, 0, 0));
+
+    for (x, fraction) in string_fractions(bytes, config.min_string, chunk).into_iter().enumerate()
+    {
+        let height = (fraction * STRINGS_HEIGHT as f64).round() as usize;
+
+        // at least a pixel tall so columns without any strings still show where the file is
+        let color = config.colormap.color_fraction(fraction);
+        image.fill_rect(Pos2{x, y: STRINGS_HEIGHT - height.max(1)}, Pos2{x: 1, y: height.max(1)}, color);
+    }
+
+    image
+}
+
+// entropy, printable fraction and nonzero fraction of the same chunks stacked on top of each other
+fn render_strip(bytes: &[u8], width: usize) -> Image<Color>
+{
+    let strip_height = 24;
+    let gap = 2;
+
+    let chunk = bytes_per_cell(bytes.len(), width);
+
+    let entropies = sliding_entropy(bytes, chunk, chunk).into_iter().map(|x| x / 8.0).collect();
+    let printable = bytes.chunks(chunk).map(printable_fraction).collect();
+    let nonzero = bytes.chunks(chunk).map(nonzero_fraction).collect();
+
+    let strips: [(Colormap, Vec<f64>); 3] = [
+        (Colormap::Magma, entropies),
+        (Colormap::Viridis, printable),
+        (Colormap::Hot, nonzero)
+    ];
+
+    let height = strips.len() * strip_height + (strips.len() - 1) * gap;
+    let mut image = Image::new(width, height, Color::RGB(0, 0, 0));
+
+    for (row, (colormap, values)) in strips.iter().enumerate()
+    {
+        let y = row * (strip_height + gap);
+
+        values.iter().enumerate().for_each(|(x, &value)|
+        {
+            image.fill_rect(Pos2{x, y}, Pos2{x: 1, y: strip_height}, colormap.color_fraction(value));
+        });
+    }
+
+    image
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn split_keeps_every_pair()
+    {
+        let bytes = [1, 1, 1, 2, 2];
+
+        let total = |counts: &Image<u32>| counts.data().iter().sum::<u32>();
+
+        let (before, after) = split_counts(&bytes, 3);
+        assert_eq!((total(&before), total(&after)), (2, 2));
+        assert_eq!(after[Pos2{x: 1, y: 2}], 1);
+
+        for split in 0..=bytes.len()
+        {
+            let (before, after) = split_counts(&bytes, split);
+
+            assert_eq!(total(&before) + total(&after), bytes.len() as u32 - 1, "split at {split}");
+        }
+    }
+
+    #[test]
+    fn records_with_a_partial_one()
+    {
+        let args = ["binvis", "input", "--mode", "records", "--record-size", "16", "--record-stat", "checksum"];
+        let config = Config::parse(args.into_iter().map(String::from)).unwrap();
+
+        let mut bytes: Vec<u8> = (0..10).flat_map(|_| 0..16).collect();
+        bytes.extend([1, 2, 3]);
+
+        let image = render(&bytes, &config);
+        let bytes_start = RECORD_STAT_WIDTH + RECORD_GAP;
+        assert_eq!((image.width(), image.height()), (bytes_start + 16, 11));
+
+        // same records get the same checksum color
+        assert_eq!(image[Pos2{x: 0, y: 0}], image[Pos2{x: 0, y: 9}]);
+        assert_ne!(image[Pos2{x: 0, y: 0}], image[Pos2{x: 0, y: 10}]);
+
+        // the missing part of the last record keeps the divider color
+        assert_eq!(image[Pos2{x: bytes_start + 5, y: 10}], image[Pos2{x: RECORD_STAT_WIDTH, y: 10}]);
+    }
+}
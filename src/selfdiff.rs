@@ -0,0 +1,83 @@
+const AUTO_LAG_MAX: usize = 1024;
+const AUTO_LAG_SAMPLE: usize = 1 << 15;
+
+// the lag with the most matching bytes in the start of the file
+pub fn auto_lag(bytes: &[u8]) -> usize
+{
+    let sample = &bytes[..bytes.len().min(AUTO_LAG_SAMPLE)];
+
+    (1..=AUTO_LAG_MAX.min(sample.len().saturating_sub(1)).max(1)).max_by_key(|&lag|
+    {
+        let matches = sample.iter().zip(sample.iter().skip(lag)).filter(|(a, b)| a == b).count();
+
+        // prefer the smaller lag on ties since multiples of a period match just as well
+        (matches, usize::MAX - lag)
+    }).unwrap_or(1)
+}
+
+// for every cell the fraction of its bytes that match the byte lag after them
+pub fn match_fractions(bytes: &[u8], lag: usize, chunk: usize) -> Vec<f64>
+{
+    let compared = bytes.len().saturating_sub(lag);
+
+    (0..compared).step_by(chunk).map(|start|
+    {
+        let end = (start + chunk).min(compared);
+
+        let matches = (start..end).filter(|&i| bytes[i] == bytes[i + lag]).count();
+
+        matches as f64 / (end - start) as f64
+    }).collect()
+}
+
+// for every cell the average number of bits (as a fraction of 8) that flip going to the next byte
+pub fn hamming_fractions(bytes: &[u8], chunk: usize) -> Vec<f64>
+{
+    let compared = bytes.len().saturating_sub(1);
+
+    (0..compared).step_by(chunk).map(|start|
+    {
+        let end = (start + chunk).min(compared);
+
+        let flipped: u32 = (start..end).map(|i| (bytes[i] ^ bytes[i + 1]).count_ones()).sum();
+
+        flipped as f64 / ((end - start) * 8) as f64
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn finds_period()
+    {
+        let bytes: Vec<u8> = (0..2000_u32).map(|i| ((i % 37) * 7 % 256) as u8).collect();
+
+        assert_eq!(auto_lag(&bytes), 37);
+
+        let fractions = match_fractions(&bytes, 37, 100);
+        assert!(fractions.iter().all(|&x| x == 1.0));
+
+        let fractions = match_fractions(&bytes, 5, 100);
+        assert!(fractions.iter().all(|&x| x < 0.1));
+    }
+
+    #[test]
+    fn short_input()
+    {
+        assert!(match_fractions(&[1, 2, 3], 10, 1).is_empty());
+        assert_eq!(auto_lag(&[]), 1);
+        assert_eq!(auto_lag(&[4]), 1);
+        assert!(hamming_fractions(&[4], 1).is_empty());
+    }
+
+    #[test]
+    fn bit_flips()
+    {
+        assert_eq!(hamming_fractions(&[0, 0, 0, 0], 3), vec![0.0]);
+        assert_eq!(hamming_fractions(&[0, 255, 0, 255, 0], 4), vec![1.0]);
+        assert_eq!(hamming_fractions(&[0b0000, 0b0011, 0b0011], 1), vec![0.25, 0.0]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::f64::consts::PI;
+
+use rustfft::{FftPlanner, num_complex::Complex};
+
+use crate::{
+    image::{Image, Pos2},
+    layout::bytes_per_cell
+};
+
+
+// one column per window of fft_size bytes (spread evenly over the file when it doesnt fit)
+// and one row per frequency bin with the lowest at the bottom, values are log magnitudes from 0 to 1
+pub fn spectrogram(bytes: &[u8], fft_size: usize, columns: usize) -> Image<f64>
+{
+    let bins = fft_size / 2;
+
+    let step = bytes_per_cell(bytes.len(), columns).max(fft_size);
+    let columns = bytes.len().div_ceil(step).clamp(1, columns);
+
+    let fft = FftPlanner::new().plan_fft_forward(fft_size);
+
+    let window: Vec<f64> = (0..fft_size).map(|i|
+    {
+        0.5 - 0.5 * (2.0 * PI * i as f64 / fft_size as f64).cos()
+    }).collect();
+
+    let mut image = Image::new(columns, bins, 0.0);
+
+    let mut buffer = vec![Complex::new(0.0, 0.0); fft_size];
+    for x in 0..columns
+    {
+        let start = x * step;
+        let chunk = &bytes[start.min(bytes.len())..(start + fft_size).min(bytes.len())];
+
+        buffer.iter_mut().enumerate().for_each(|(i, value)|
+        {
+            // centered around zero so the dc bin doesnt drown everything out
+            let sample = chunk.get(i).map(|&x| x as f64 - 127.5).unwrap_or(0.0);
+
+            *value = Complex::new(sample * window[i], 0.0);
+        });
+
+        fft.process(&mut buffer);
+
+        for bin in 0..bins
+        {
+            image[Pos2{x, y: bins - 1 - bin}] = buffer[bin].norm().ln_1p();
+        }
+    }
+
+    let highest = image.data().iter().copied().fold(0.0, f64::max);
+
+    if highest > 0.0
+    {
+        image.map(|x| x / highest)
+    }
+    else
+    {
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn finds_period()
+    {
+        let fft_size = 64;
+
+        // a square wave with a period of 8 bytes
+        let bytes: Vec<u8> = (0..fft_size * 4).map(|i| ((i % 8 < 4) as u8) * 255).collect();
+
+        let image = spectrogram(&bytes, fft_size, 4);
+        assert_eq!((image.width(), image.height()), (4, fft_size / 2));
+
+        let column: Vec<f64> = (0..image.height()).map(|y| image[Pos2{x: 0, y}]).collect();
+
+        let loudest = column.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+
+        // bin fft_size / 8 counted from the bottom
+        assert_eq!(image.height() - 1 - loudest, fft_size / 8);
+    }
+}
@@ -0,0 +1,189 @@
+// rough groups of byte values that tell apart padding, text, binary structures and compressed data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass
+{
+    Null,
+    Control,
+    Printable,
+    High
+}
+
+impl ByteClass
+{
+    pub const ALL: [Self; 4] = [Self::Null, Self::Control, Self::Printable, Self::High];
+
+    pub fn classify(byte: u8) -> Self
+    {
+        match byte
+        {
+            0 => Self::Null,
+            x if is_printable(x) => Self::Printable,
+            0x80.. => Self::High,
+            _ => Self::Control
+        }
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Null => "NULL",
+            Self::Control => "CONTROL",
+            Self::Printable => "PRINTABLE",
+            Self::High => "HIGH"
+        }
+    }
+
+    // the class most of the bytes fall into, ties go to the earlier class
+    pub fn majority(bytes: &[u8]) -> Self
+    {
+        let mut counts = [0_usize; 4];
+        bytes.iter().for_each(|&x| counts[Self::classify(x) as usize] += 1);
+
+        Self::ALL.into_iter().rev().max_by_key(|&class| counts[class as usize]).unwrap()
+    }
+}
+
+// how many times each byte value shows up
+pub fn histogram(bytes: &[u8]) -> [u64; 256]
+{
+    let mut counts = [0; 256];
+    bytes.iter().for_each(|&x| counts[x as usize] += 1);
+
+    counts
+}
+
+pub fn is_printable(byte: u8) -> bool
+{
+    matches!(byte, 0x20..=0x7e | b'\t' | b'\n' | b'\r')
+}
+
+fn fraction(bytes: &[u8], predicate: impl Fn(u8) -> bool) -> f64
+{
+    if bytes.is_empty()
+    {
+        return 0.0;
+    }
+
+    bytes.iter().filter(|&&x| predicate(x)).count() as f64 / bytes.len() as f64
+}
+
+pub fn printable_fraction(bytes: &[u8]) -> f64
+{
+    fraction(bytes, is_printable)
+}
+
+// how much of it isnt zero padding
+pub fn nonzero_fraction(bytes: &[u8]) -> f64
+{
+    fraction(bytes, |x| x != 0)
+}
+
+// how often a byte is the same as the one before it
+pub fn repetition_fraction(bytes: &[u8]) -> f64
+{
+    if bytes.len() < 2
+    {
+        return 0.0;
+    }
+
+    let repeats = bytes.windows(2).filter(|pair| pair[0] == pair[1]).count();
+
+    repeats as f64 / (bytes.len() - 1) as f64
+}
+
+// for every chunk the fraction of its bytes that are in a run of at least min_length printable
+// bytes (like what the strings tool finds), runs going across chunks count for both
+pub fn string_fractions(bytes: &[u8], min_length: usize, chunk: usize) -> Vec<f64>
+{
+    let mut counts = vec![0; bytes.len().div_ceil(chunk)];
+
+    let mut add_run = |start: usize, end: usize|
+    {
+        if end - start < min_length
+        {
+            return;
+        }
+
+        for (index, count) in counts.iter_mut().enumerate().take(end.div_ceil(chunk)).skip(start / chunk)
+        {
+            *count += end.min((index + 1) * chunk) - start.max(index * chunk);
+        }
+    };
+
+    let mut run_start = None;
+    for (index, &byte) in bytes.iter().enumerate()
+    {
+        match (is_printable(byte), run_start)
+        {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) =>
+            {
+                add_run(start, index);
+                run_start = None;
+            },
+            _ => ()
+        }
+    }
+
+    if let Some(start) = run_start
+    {
+        add_run(start, bytes.len());
+    }
+
+    counts.into_iter().enumerate().map(|(index, count)|
+    {
+        let length = chunk.min(bytes.len() - index * chunk);
+
+        count as f64 / length as f64
+    }).collect()
+}
+
+pub fn average(bytes: &[u8]) -> f64
+{
+    if bytes.is_empty()
+    {
+        return 0.0;
+    }
+
+    bytes.iter().map(|&x| x as u64).sum::<u64>() as f64 / bytes.len() as f64
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn byte_classes()
+    {
+        let classes = [0, 1, b'\n', b'a', 0x7f, 0x80, 0xff].map(ByteClass::classify);
+
+        assert_eq!(classes, [
+            ByteClass::Null,
+            ByteClass::Control,
+            ByteClass::Printable,
+            ByteClass::Printable,
+            ByteClass::Control,
+            ByteClass::High,
+            ByteClass::High
+        ]);
+
+        assert_eq!(ByteClass::majority(b"ab\0\0\xff"), ByteClass::Null);
+        assert_eq!(ByteClass::majority(b"a\0"), ByteClass::Null);
+        assert_eq!(ByteClass::majority(b"abc\0\0"), ByteClass::Printable);
+    }
+
+    #[test]
+    fn string_runs()
+    {
+        // a run of 6 going across the first two chunks, one too short to count and one going up to the end
+        let bytes = b"\0\0hello!\0ab\0\0\0\0xyzw";
+
+        assert_eq!(string_fractions(bytes, 4, 4), vec![0.5, 1.0, 0.0, 0.25, 1.0]);
+        assert_eq!(string_fractions(bytes, 2, 4), vec![0.5, 1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(string_fractions(bytes, 7, 4), vec![0.0; 5]);
+        assert_eq!(string_fractions(b"abcde", 1, 2), vec![1.0, 1.0, 1.0]);
+        assert!(string_fractions(b"", 4, 4).is_empty());
+    }
+}
@@ -0,0 +1,731 @@
+use std::{
+    io,
+    mem,
+    panic,
+    path::PathBuf,
+    thread,
+    time::{Instant, Duration},
+    sync::mpsc::{Receiver, RecvTimeoutError}
+};
+
+use sdl2::{
+    Sdl,
+    EventPump,
+    pixels::{PixelFormatEnum, Color},
+    event::Event,
+    keyboard::Keycode,
+    rect::Rect,
+    video::{WindowContext, Window},
+    render::{Canvas, TextureCreator, Texture}
+};
+
+use crate::{
+    put_points,
+    digraph_counts,
+    config::Config,
+    budget::Loaded,
+    counts::save_counts,
+    export::dump_counts,
+    image::{Image, Pos2},
+    overlay::{HeatTrail, LiveHistogram},
+    render::{render, render_digraph, VisualizationMode}
+};
+
+
+const FRAME_TIME: Duration = Duration::from_millis(1000 / 60);
+
+// dont rerender on every tiny chunk a stream gives
+const STREAM_RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+// modes other than digraph render the whole stream again each time which gets slower as it grows,
+// so at least this many times as long as the last render took passes before the next one
+const STREAM_RENDER_BACKOFF: u32 = 4;
+
+// tinier windows are hard to even find on the screen, smaller images just get stretched to fill this
+const MIN_WINDOW_SIZE: u32 = 64;
+
+// how many bytes get processed between checking if the window got closed
+const EVENTS_CHECK_BYTES: usize = 1 << 20;
+
+// counts_1.csv, counts_2.csv and so on, whichever doesnt exist yet
+fn dump_path() -> PathBuf
+{
+    (1..).map(|index| PathBuf::from(format!("counts_{index}.csv"))).find(|path| !path.exists()).unwrap()
+}
+
+// biggest rect with the source aspect ratio that fits centered in the destination
+pub fn letterbox(source: (u32, u32), destination: (u32, u32)) -> Rect
+{
+    let (source_width, source_height) = (source.0.max(1) as f64, source.1.max(1) as f64);
+    let (destination_width, destination_height) = destination;
+
+    let scale = (destination_width as f64 / source_width).min(destination_height as f64 / source_height);
+
+    let width = ((source_width * scale).round() as u32).clamp(1, destination_width.max(1));
+    let height = ((source_height * scale).round() as u32).clamp(1, destination_height.max(1));
+
+    let x = (destination_width.saturating_sub(width) / 2) as i32;
+    let y = (destination_height.saturating_sub(height) / 2) as i32;
+
+    Rect::new(x, y, width, height)
+}
+
+// none (with a warning) if theres no driver with that name so sdl picks one itself
+fn render_driver_index(name: &str) -> Option<u32>
+{
+    let index = sdl2::render::drivers().position(|driver| driver.name == name);
+
+    if index.is_none()
+    {
+        let names: Vec<_> = sdl2::render::drivers().map(|driver| driver.name).collect();
+        eprintln!("warning: no render driver named {name} (available: {}), using the default", names.join(", "));
+    }
+
+    index.map(|index| index as u32)
+}
+
+pub struct WindowHolder
+{
+    ctx: Sdl,
+    canvas: Canvas<Window>,
+    keep_aspect: bool
+}
+
+impl WindowHolder
+{
+    // config.keep_aspect letterboxes the image when the window is resized instead of stretching it
+    // and config.render_driver picks the sdl renderer (printing the available ones with config.verbose)
+    pub fn new(width: u32, height: u32, config: &Config) -> Self
+    {
+        if width < MIN_WINDOW_SIZE || height < MIN_WINDOW_SIZE
+        {
+            eprintln!(
+                "warning: a {width}x{height} window is too small, making it at least {MIN_WINDOW_SIZE}x{MIN_WINDOW_SIZE}"
+            );
+        }
+
+        let width = width.max(MIN_WINDOW_SIZE);
+        let height = height.max(MIN_WINDOW_SIZE);
+
+        let ctx = sdl2::init().unwrap();
+
+        let video = ctx.video().unwrap();
+
+        let window = video.window("binary visualizer!", width, height)
+            .resizable()
+            .build()
+            .unwrap();
+
+        if config.verbose
+        {
+            let names: Vec<_> = sdl2::render::drivers().map(|driver| driver.name).collect();
+            eprintln!("available render drivers: {}", names.join(", "));
+        }
+
+        let mut canvas = window.into_canvas();
+        if let Some(index) = config.render_driver.as_deref().and_then(render_driver_index)
+        {
+            canvas = canvas.index(index);
+        }
+
+        let canvas = canvas.build().unwrap();
+
+        if config.verbose
+        {
+            eprintln!("using render driver: {}", canvas.info().name);
+        }
+
+        Self{ctx, canvas, keep_aspect: config.keep_aspect}
+    }
+
+    pub fn events(&self) -> EventPump
+    {
+        self.ctx.event_pump().unwrap()
+    }
+
+    pub fn texture_creator(&self) -> TextureCreator<WindowContext>
+    {
+        self.canvas.texture_creator()
+    }
+
+    pub fn set_title(&mut self, title: &str)
+    {
+        self.canvas.window_mut().set_title(title).unwrap();
+    }
+
+    fn destination(&self, source: (u32, u32), target: (u32, u32)) -> Rect
+    {
+        if self.keep_aspect
+        {
+            letterbox(source, target)
+        }
+        else
+        {
+            Rect::new(0, 0, target.0.max(1), target.1.max(1))
+        }
+    }
+
+    // which image cell a point in window coordinates (like mouse events) lands on
+    pub fn window_to_image(&self, pos: (i32, i32), image_size: (usize, usize)) -> Option<Pos2<usize>>
+    {
+        let rect = self.destination(
+            (image_size.0 as u32, image_size.1 as u32),
+            self.canvas.window().size()
+        );
+
+        let x = pos.0 - rect.x();
+        let y = pos.1 - rect.y();
+
+        if x < 0 || y < 0 || x >= rect.width() as i32 || y >= rect.height() as i32
+        {
+            return None;
+        }
+
+        Some(Pos2{
+            x: x as usize * image_size.0 / rect.width() as usize,
+            y: y as usize * image_size.1 / rect.height() as usize
+        })
+    }
+
+    pub fn draw(&mut self, texture: &Texture)
+    {
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+
+        let query = texture.query();
+        let output = self.canvas.output_size().unwrap();
+
+        let destination = self.destination((query.width, query.height), output);
+
+        self.canvas.copy(texture, None, destination).unwrap();
+
+        self.canvas.present();
+    }
+}
+
+// digraph counts only get updated with the new bytes instead of rerendering everything
+struct Progress
+{
+    counts: Image<u32>,
+    counted: usize
+}
+
+impl Progress
+{
+    fn new() -> Self
+    {
+        Self{counts: Image::new(256, 256, 0), counted: 0}
+    }
+}
+
+pub struct DrawerWindow<'a>
+{
+    events: EventPump,
+    window: WindowHolder,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    texture: Texture<'a>,
+    image: Image,
+    heat_trail: Option<HeatTrail>,
+    histogram: LiveHistogram,
+    histogram_visible: bool,
+    keys: Vec<Keycode>,
+    last_frame: Instant
+}
+
+impl<'a> DrawerWindow<'a>
+{
+    pub fn new(
+        window: WindowHolder,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        image: Image,
+        config: &Config
+    ) -> Self
+    {
+        let image = Self::drawable(image);
+        let texture = Self::create_texture(texture_creator, &image);
+
+        let heat_trail = config.heat_trail.then(|| HeatTrail::new(image.width(), image.height()));
+
+        let histogram_size = Pos2{x: config.histogram_width, y: config.histogram_height};
+        let histogram = LiveHistogram::new(config.histogram_corner, histogram_size);
+
+        let mut this = Self{
+            events: window.events(),
+            window,
+            texture_creator,
+            texture,
+            image: Image::new(0, 0, Color::RGB(0, 0, 0)),
+            heat_trail,
+            histogram,
+            histogram_visible: config.live_histogram,
+            keys: Vec::new(),
+            last_frame: Instant::now()
+        };
+
+        this.update(image);
+
+        this
+    }
+
+    // sdl cant make a texture without any pixels
+    fn drawable(image: Image) -> Image
+    {
+        if image.width() == 0 || image.height() == 0
+        {
+            Image::new(1, 1, Color::RGB(0, 0, 0))
+        }
+        else
+        {
+            image
+        }
+    }
+
+    fn create_texture(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        image: &Image
+    ) -> Texture<'a>
+    {
+        texture_creator
+            .create_texture_static(
+                PixelFormatEnum::RGBA32,
+                image.width() as u32,
+                image.height() as u32
+            ).unwrap()
+    }
+
+    pub fn update(&mut self, image: Image)
+    {
+        let image = Self::drawable(image);
+
+        let query = self.texture.query();
+        if (query.width as usize, query.height as usize) != (image.width(), image.height())
+        {
+            self.texture = Self::create_texture(self.texture_creator, &image);
+
+            if let Some(heat_trail) = self.heat_trail.as_mut()
+            {
+                heat_trail.resize(image.width(), image.height());
+            }
+        }
+
+        self.image = image;
+
+        self.upload();
+    }
+
+    // bytes[start..end] being whats shown, only the bytes that werent there last time get counted
+    pub fn update_histogram(&mut self, bytes: &[u8], start: usize, end: usize)
+    {
+        self.histogram.update(bytes, start, end);
+
+        if self.histogram_visible
+        {
+            self.upload();
+        }
+    }
+
+    // for inputs that were only accumulated so theres no bytes to count
+    pub fn set_histogram(&mut self, counts: [u64; 256])
+    {
+        self.histogram.set(counts);
+
+        if self.histogram_visible
+        {
+            self.upload();
+        }
+    }
+
+    fn upload(&mut self)
+    {
+        let data = if self.heat_trail.is_none() && !self.histogram_visible
+        {
+            self.image.data_raw()
+        }
+        else
+        {
+            let mut image = self.image.clone();
+
+            if let Some(heat_trail) = self.heat_trail.as_ref()
+            {
+                image.blend(&heat_trail.overlay());
+            }
+
+            if self.histogram_visible
+            {
+                self.histogram.draw(&mut image);
+            }
+
+            image.data_raw()
+        };
+
+        self.texture.update(None, &data, self.image.width() * 4).unwrap();
+    }
+
+    // returns true if the window got closed
+    fn handle_events(&mut self) -> bool
+    {
+        let mut quit = false;
+        let mut toggled_histogram = false;
+
+        let image_size = (self.image.width(), self.image.height());
+        for event in self.events.poll_iter()
+        {
+            match event
+            {
+                Event::Quit{..} => quit = true,
+                Event::KeyDown{keycode: Some(Keycode::H), ..} =>
+                {
+                    self.histogram_visible = !self.histogram_visible;
+                    toggled_histogram = true;
+                },
+                Event::KeyDown{keycode: Some(key), ..} => self.keys.push(key),
+                Event::MouseMotion{x, y, ..} =>
+                {
+                    if let Some(heat_trail) = self.heat_trail.as_mut()
+                    {
+                        if let Some(pos) = self.window.window_to_image((x, y), image_size)
+                        {
+                            heat_trail.touch(pos);
+                        }
+                    }
+                },
+                _ => ()
+            }
+        }
+
+        if toggled_histogram
+        {
+            self.upload();
+        }
+
+        quit
+    }
+
+    fn draw(&mut self)
+    {
+        let now = Instant::now();
+        let elapsed = now - self.last_frame;
+        self.last_frame = now;
+
+        if let Some(heat_trail) = self.heat_trail.as_mut()
+        {
+            heat_trail.decay(elapsed);
+
+            self.upload();
+        }
+
+        self.window.draw(&self.texture);
+    }
+
+    // d saves the counts of whats shown to a csv in the working directory, only built if the key got pressed
+    fn handle_dump_key(keys: &[Keycode], counts: impl FnOnce() -> Image<u32>)
+    {
+        if !keys.contains(&Keycode::D)
+        {
+            return;
+        }
+
+        let path = dump_path();
+        match save_counts(&path, &counts())
+        {
+            Ok(()) => println!("saved counts to {}", path.display()),
+            Err(err) => eprintln!("cant save counts to {}: {err}", path.display())
+        }
+    }
+
+    // --dump-counts for a stream, which only has all of its bytes once it ends
+    fn dump_stream(bytes: &[u8], config: &Config)
+    {
+        if let Err(err) = dump_counts(config, || digraph_counts(bytes))
+        {
+            eprintln!("cant save counts: {err}");
+        }
+    }
+
+    pub fn wait_exit(mut self, input: &Loaded)
+    {
+        loop
+        {
+            if self.handle_events()
+            {
+                return;
+            }
+
+            Self::handle_dump_key(&mem::take(&mut self.keys), || input.digraph_counts());
+
+            self.draw();
+
+            thread::sleep(FRAME_TIME);
+        }
+    }
+
+    // none if the window got closed while rendering
+    fn render_progress(&mut self, progress: &mut Progress, bytes: &[u8], config: &Config) -> Option<Image>
+    {
+        if config.mode != VisualizationMode::Digraph
+        {
+            return self.render_polling(bytes, config);
+        }
+
+        if bytes.len() < progress.counted
+        {
+            *progress = Progress::new();
+        }
+
+        while progress.counted < bytes.len()
+        {
+            let end = (progress.counted + EVENTS_CHECK_BYTES).min(bytes.len());
+
+            // starts one byte back for the pair crossing into the new bytes
+            put_points(&mut progress.counts, &bytes[progress.counted.saturating_sub(1)..end]);
+            progress.counted = end;
+
+            if self.handle_events()
+            {
+                return None;
+            }
+        }
+
+        Some(render_digraph(&progress.counts, bytes.len(), config))
+    }
+
+    // other modes cant be split into chunks from out here so they render on another thread while the
+    // events keep getting checked, none if the window got closed (the render is left to finish on its own)
+    fn render_polling(&mut self, bytes: &[u8], config: &Config) -> Option<Image>
+    {
+        if bytes.len() <= EVENTS_CHECK_BYTES
+        {
+            return Some(render(bytes, config));
+        }
+
+        let (bytes, config) = (bytes.to_vec(), config.clone());
+        let handle = thread::spawn(move || render(&bytes, &config));
+
+        while !handle.is_finished()
+        {
+            if self.handle_events()
+            {
+                return None;
+            }
+
+            thread::sleep(FRAME_TIME);
+        }
+
+        Some(handle.join().unwrap_or_else(|err| panic::resume_unwind(err)))
+    }
+
+    // reveals the file a chunk at a time, space pauses and the arrows step while paused
+    // with an animate window only the last that many bytes are shown instead of everything so far
+    pub fn run_animation(mut self, bytes: &[u8], config: &Config)
+    {
+        let chunk = bytes.len().div_ceil(config.animate_steps).max(1);
+        let total = bytes.len().div_ceil(chunk);
+
+        // which bytes are on screen after the first shown chunks
+        let shown_range = |shown: usize|
+        {
+            let end = (shown * chunk).min(bytes.len());
+            let start = config.animate_window.map(|window| end.saturating_sub(window)).unwrap_or(0);
+
+            start..end
+        };
+
+        let mut progress = Progress::new();
+
+        let mut shown = 0;
+        let mut rendered = None;
+        let mut paused = false;
+
+        loop
+        {
+            if self.handle_events()
+            {
+                return;
+            }
+
+            let keys = mem::take(&mut self.keys);
+
+            Self::handle_dump_key(&keys, || digraph_counts(&bytes[shown_range(shown)]));
+
+            let mut title_changed = false;
+            for key in keys
+            {
+                match key
+                {
+                    Keycode::Space =>
+                    {
+                        paused = !paused;
+                        title_changed = true;
+                    },
+                    Keycode::Right if paused => shown = (shown + 1).min(total),
+                    Keycode::Left if paused => shown = shown.saturating_sub(1),
+                    _ => ()
+                }
+            }
+
+            if !paused && shown < total
+            {
+                shown += 1;
+            }
+
+            if rendered != Some(shown)
+            {
+                let range = shown_range(shown);
+                let (start, end) = (range.start, range.end);
+
+                self.update_histogram(bytes, start, end);
+
+                // a sliding window cant be counted incrementally, it gets rendered from scratch every step
+                let image = if config.animate_window.is_some()
+                {
+                    self.render_polling(&bytes[start..end], config)
+                }
+                else
+                {
+                    self.render_progress(&mut progress, &bytes[..end], config)
+                };
+
+                let Some(image) = image
+                else
+                {
+                    return;
+                };
+
+                self.update(image);
+
+                rendered = Some(shown);
+                title_changed = true;
+            }
+
+            if title_changed
+            {
+                let end = (shown * chunk).min(bytes.len());
+                let percentage = end as f64 / bytes.len().max(1) as f64 * 100.0;
+
+                let paused = if paused { ", paused" } else { "" };
+                self.window.set_title(&format!(
+                    "binary visualizer! (offset {end} / {}, {percentage:.1}%{paused})",
+                    bytes.len()
+                ));
+            }
+
+            self.draw();
+
+            thread::sleep(FRAME_TIME);
+        }
+    }
+
+    // keeps drawing while chunks come in, waits at most a frame for new data so it never blocks
+    pub fn run_stream(mut self, receiver: Receiver<io::Result<Vec<u8>>>, config: &Config)
+    {
+        let mut bytes = Vec::new();
+
+        let mut progress = Progress::new();
+
+        let mut streaming = true;
+        let mut changed = false;
+        let mut last_render = Instant::now();
+        let mut render_took = Duration::ZERO;
+
+        loop
+        {
+            if self.handle_events()
+            {
+                // following never ends by itself so closing the window is where it ends
+                if streaming
+                {
+                    Self::dump_stream(&bytes, config);
+                }
+
+                return;
+            }
+
+            let was_streaming = streaming;
+
+            let frame_end = Instant::now() + FRAME_TIME;
+            while streaming
+            {
+                let timeout = frame_end.saturating_duration_since(Instant::now());
+                if timeout.is_zero()
+                {
+                    break;
+                }
+
+                match receiver.recv_timeout(timeout)
+                {
+                    Ok(Ok(chunk)) =>
+                    {
+                        bytes.extend(chunk);
+                        changed = true;
+                    },
+                    Ok(Err(err)) =>
+                    {
+                        eprintln!("error reading {}: {err}", config.input);
+                        streaming = false;
+                    },
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => streaming = false
+                }
+            }
+
+            if was_streaming && !streaming
+            {
+                Self::dump_stream(&bytes, config);
+            }
+
+            Self::handle_dump_key(&mem::take(&mut self.keys), || digraph_counts(&bytes));
+
+            let interval = STREAM_RENDER_INTERVAL.max(render_took * STREAM_RENDER_BACKOFF);
+
+            let render_due = last_render.elapsed() >= interval;
+            if changed && (render_due || !streaming)
+            {
+                let render_start = Instant::now();
+
+                self.update_histogram(&bytes, 0, bytes.len());
+
+                let Some(image) = self.render_progress(&mut progress, &bytes, config)
+                else
+                {
+                    if streaming
+                    {
+                        Self::dump_stream(&bytes, config);
+                    }
+
+                    return;
+                };
+
+                self.update(image);
+
+                let state = if streaming { "streaming" } else { "done" };
+                self.window.set_title(&format!("binary visualizer! ({} bytes, {state})", bytes.len()));
+
+                changed = false;
+                last_render = Instant::now();
+                render_took = last_render - render_start;
+            }
+
+            self.draw();
+
+            if !streaming
+            {
+                thread::sleep(FRAME_TIME);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn letterbox_fits()
+    {
+        assert_eq!(letterbox((256, 256), (512, 512)), Rect::new(0, 0, 512, 512));
+        assert_eq!(letterbox((256, 256), (800, 400)), Rect::new(200, 0, 400, 400));
+        assert_eq!(letterbox((256, 76), (512, 512)), Rect::new(0, 180, 512, 152));
+        assert_eq!(letterbox((256, 256), (0, 0)), Rect::new(0, 0, 1, 1));
+    }
+}
@@ -0,0 +1,70 @@
+use std::{env, fs, process};
+
+use binvis::{
+    config::Config,
+    render::{render, VisualizationMode},
+    export::{export, load_png}
+};
+
+
+#[test]
+fn export_never_initializes_sdl()
+{
+    let directory = env::temp_dir();
+    let input = directory.join(format!("binvis_headless_input_{}", process::id()));
+    let output = directory.join(format!("binvis_headless_output_{}.png", process::id()));
+
+    let bytes: Vec<u8> = (0..100_000_u32).map(|x| (x.wrapping_mul(2654435761) >> 24) as u8).collect();
+    fs::write(&input, &bytes).unwrap();
+
+    for &(mode, _) in VisualizationMode::NAMES
+    {
+        let args = [
+            "binvis",
+            input.to_str().unwrap(),
+            "--mode",
+            mode,
+            "--record-size",
+            "100",
+            "--output",
+            output.to_str().unwrap()
+        ];
+
+        let config = Config::parse(args.into_iter().map(String::from)).unwrap();
+
+        export(&config, config.output.as_ref().unwrap()).unwrap();
+
+        let exported = load_png(&output).unwrap();
+        let expected = render(&bytes, &config);
+
+        assert_eq!((exported.width(), exported.height()), (expected.width(), expected.height()));
+        assert!(exported.data() == expected.data(), "{mode} export differs from the render");
+    }
+
+    fs::remove_file(&input).unwrap();
+    fs::remove_file(&output).unwrap();
+
+    assert_eq!(unsafe{ sdl2::sys::SDL_WasInit(0) }, 0, "exporting initialized sdl");
+}
+
+#[test]
+fn tiny_sizes_render_every_mode()
+{
+    let inputs: [Vec<u8>; 3] = [Vec::new(), vec![7], (0..5000_u32).map(|x| (x % 253) as u8).collect()];
+
+    for &(mode, _) in VisualizationMode::NAMES
+    {
+        for size in ["1", "2"]
+        {
+            let args = ["binvis", "input", "--mode", mode, "--size", size, "--fft-size", "2", "--record-size", "3"];
+            let config = Config::parse(args.into_iter().map(String::from)).unwrap();
+
+            for bytes in &inputs
+            {
+                let image = render(bytes, &config);
+
+                assert!(image.width() > 0 && image.height() > 0, "{mode} at size {size} rendered an empty image");
+            }
+        }
+    }
+}